@@ -1,7 +1,10 @@
 #ifndef LIBREX_H
 #define LIBREX_H
 
+struct bpf_link;
+struct bpf_map;
 struct bpf_object;
+struct bpf_program;
 struct rex_obj;
 
 #ifdef __cplusplus
@@ -16,6 +19,13 @@ rex_obj_load(const char *file_path);
 [[nodiscard, gnu::visibility("default")]] struct bpf_object *
 rex_obj_get_bpf(struct rex_obj *obj);
 
+[[nodiscard, gnu::visibility("default")]] struct bpf_link *
+rex_prog_attach_usdt(const struct bpf_program *prog, int pid,
+                     const char *binary_path);
+
+[[nodiscard, gnu::visibility("default")]] struct bpf_link *
+rex_map_attach_struct_ops(const struct bpf_map *map);
+
 #ifdef __cplusplus
 }
 #endif
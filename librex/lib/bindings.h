@@ -26,6 +26,17 @@ struct elf_state {
   int arena_data_shndx;
 };
 
+enum sec_def_flags {
+  SEC_NONE = 0,
+  SEC_EXP_ATTACH_OPT = 1,
+  SEC_ATTACHABLE = 2,
+  SEC_ATTACHABLE_OPT = SEC_ATTACHABLE | SEC_EXP_ATTACH_OPT,
+  SEC_ATTACH_BTF = 4,
+  SEC_SLEEPABLE = 8,
+  SEC_XDP_FRAGS = 16,
+  SEC_USDT = 32,
+};
+
 struct bpf_sec_def {
   char *sec;
   enum bpf_prog_type prog_type;
@@ -238,4 +249,12 @@ struct bpf_map {
   __u64 map_extra;
 };
 
+struct bpf_link {
+  int (*detach)(struct bpf_link *link);
+  void (*dealloc)(struct bpf_link *link);
+  char *pin_path; /* NULL, if not pinned */
+  int fd;         /* hook FD, -1 if not applicable */
+  bool disconnected;
+};
+
 #endif // _LIBREX_BINDINGS_H
@@ -1,11 +1,15 @@
 #include <fcntl.h>
+#include <fnmatch.h>
 #include <libelf.h>
+#include <bpf/btf.h>
 #include <linux/bpf.h>
+#include <linux/netfilter.h>
 #include <sys/mman.h>
 #include <sys/stat.h>
 #include <unistd.h>
 
 #include <algorithm>
+#include <charconv>
 #include <cstdint>
 #include <cstring>
 #include <filesystem>
@@ -15,15 +19,18 @@
 #include <memory>
 #include <optional>
 #include <ranges>
+#include <set>
 #include <stdexcept>
 #include <string>
 #include <system_error>
+#include <type_traits>
 #include <unordered_map>
 #include <utility>
 #include <vector>
 
 #include "bindings.h"
 #include "librex.h"
+#include "usdt.h"
 
 using namespace std::literals;
 
@@ -56,12 +63,450 @@ bool sec_def_matches(const struct bpf_sec_def *sec_def,
   return sec_name == sec_pfx;
 }
 
-/// @brief Walk through the static const struct bpf_sec_def section_defs
-/// in libbpf.c and figure out the valid bpf section
+/// @brief Splits the comma-separated list at the end of a section name
+std::vector<std::string> split_list(std::string_view list) {
+  std::vector<std::string> items;
+
+  for (auto item : std::views::split(list, ','))
+    items.emplace_back(item.begin(), item.end());
+
+  return items;
+}
+
+/// @brief Expands the kprobe.multi patterns into the traceable kernel
+/// functions they match, libbpf only takes a single pattern
+std::optional<std::vector<std::string>>
+expand_kprobe_patterns(const std::vector<std::string> &patterns) {
+  std::ifstream funcs("/sys/kernel/tracing/available_filter_functions");
+  std::set<std::string> syms;
+  std::string line;
+
+  if (!funcs)
+    funcs.open("/sys/kernel/debug/tracing/available_filter_functions");
+
+  if (!funcs) {
+    std::cerr << "kprobe.multi: failed to open available_filter_functions"
+              << std::endl;
+    return std::nullopt;
+  }
+
+  // Each line is "<function>" or "<function> [<module>]"
+  while (std::getline(funcs, line)) {
+    std::string sym = line.substr(0, line.find(' '));
+
+    for (const auto &pattern : patterns) {
+      if (!fnmatch(pattern.c_str(), sym.c_str(), 0)) {
+        syms.insert(sym);
+        break;
+      }
+    }
+  }
+
+  return std::vector<std::string>(syms.begin(), syms.end());
+}
+
+/// @brief Attaches a "rex/k[ret]probe.multi/<p1>,<p2>,..." program to all
+/// functions matching any of the patterns
+///
+/// @param cookie 1 for kretprobes, 0 otherwise
+int attach_kprobe_multi(const struct bpf_program *prog, long cookie,
+                        struct bpf_link **link) {
+  std::string_view sec_name = bpf_program__section_name(prog);
+  std::vector<const char *> sym_ptrs;
+  bpf_kprobe_multi_opts opts{};
+
+  sec_name.remove_prefix(sec_name.find(".multi/") + ".multi/"sv.size());
+  auto syms = expand_kprobe_patterns(split_list(sec_name));
+  if (!syms)
+    return -ENOENT;
+
+  if (syms->empty()) {
+    std::cerr << "kprobe.multi: no function matches " << sec_name
+              << std::endl;
+    return -ENOENT;
+  }
+
+  if (debug)
+    std::clog << "kprobe.multi: " << syms->size() << " functions" << std::endl;
+
+  for (const auto &sym : *syms)
+    sym_ptrs.push_back(sym.c_str());
+
+  opts.sz = sizeof(opts);
+  opts.syms = sym_ptrs.data();
+  opts.cnt = sym_ptrs.size();
+  opts.retprobe = cookie;
+
+  *link = bpf_program__attach_kprobe_multi_opts(prog, nullptr, &opts);
+  return libbpf_get_error(*link);
+}
+
+/// @brief Parses an unsigned number in the form of the uprobe sections,
+/// i.e. "0x<hex>" or "<decimal>"
+std::optional<unsigned long> parse_ulong(std::string_view str) {
+  int base = 10;
+  unsigned long val;
+
+  if (str.starts_with("0x")) {
+    str.remove_prefix(2);
+    base = 16;
+  }
+
+  auto [end, ec] =
+      std::from_chars(str.data(), str.data() + str.size(), val, base);
+  if (str.empty() || ec != std::errc() || end != str.data() + str.size())
+    return std::nullopt;
+
+  return val;
+}
+
+/// @brief Attaches a "rex/u[ret]probe/<binary>:<target>[@<pid>]" program,
+/// where the target is a file offset ("0x<off>") or a symbol with an optional
+/// offset ("<symbol>[+0x<off>]")
+///
+/// The symbol is resolved by libbpf against the binary on the target system
+/// when the program is attached.
+///
+/// @param cookie 1 for uretprobes, 0 otherwise
+int attach_uprobe(const struct bpf_program *prog, long cookie,
+                  struct bpf_link **link) {
+  std::string_view spec = bpf_program__section_name(prog);
+  bpf_uprobe_opts opts{};
+  std::string binary, symbol;
+  unsigned long offset = 0;
+  pid_t pid = -1;
+
+  spec.remove_prefix(spec.find('/', "rex/"sv.size()) + 1);
+
+  // A pid only has digits, which tells it apart from a symbol version, e.g.
+  // "malloc@@GLIBC_2.2.5"
+  if (size_t at = spec.rfind('@'); at != spec.npos) {
+    if (auto val = parse_ulong(spec.substr(at + 1)); val) {
+      pid = *val;
+      spec.remove_suffix(spec.size() - at);
+    }
+  }
+
+  size_t colon = spec.rfind(':');
+  if (colon == spec.npos || colon == 0 || colon == spec.size() - 1) {
+    std::cerr << "uprobe: invalid section " << bpf_program__section_name(prog)
+              << std::endl;
+    return -EINVAL;
+  }
+
+  binary = spec.substr(0, colon);
+  std::string_view target = spec.substr(colon + 1);
+
+  if (target.starts_with("0x")) {
+    auto val = parse_ulong(target);
+    if (!val) {
+      std::cerr << "uprobe: invalid offset " << target << std::endl;
+      return -EINVAL;
+    }
+    offset = *val;
+  } else {
+    size_t plus = target.find('+');
+    if (plus != target.npos) {
+      auto val = parse_ulong(target.substr(plus + 1));
+      if (!val) {
+        std::cerr << "uprobe: invalid offset " << target << std::endl;
+        return -EINVAL;
+      }
+      offset = *val;
+      target.remove_suffix(target.size() - plus);
+    }
+    symbol = target;
+  }
+
+  if (debug)
+    std::clog << "uprobe: " << binary << ":" << symbol << "+0x" << std::hex
+              << offset << std::dec << " pid=" << pid << std::endl;
+
+  opts.sz = sizeof(opts);
+  opts.retprobe = cookie;
+  opts.func_name = symbol.empty() ? nullptr : symbol.c_str();
+
+  *link = bpf_program__attach_uprobe_opts(prog, pid, binary.c_str(), offset,
+                                          &opts);
+  return libbpf_get_error(*link);
+}
+
+/// @brief A link holding the uprobes of all sites of a USDT probe
+struct usdt_link {
+  bpf_link link;
+  std::vector<bpf_link *> uprobes;
+
+  static int detach(bpf_link *link) {
+    auto *self = reinterpret_cast<usdt_link *>(link);
+    int err = 0;
+
+    for (auto *uprobe : self->uprobes) {
+      int ret = bpf_link__destroy(uprobe);
+      err = err ? err : ret;
+    }
+
+    self->uprobes.clear();
+    return err;
+  }
+
+  static void dealloc(bpf_link *link) {
+    delete reinterpret_cast<usdt_link *>(link);
+  }
+};
+
+static_assert(std::is_standard_layout_v<usdt_link>);
+
+/// @brief Attaches the program to all sites of the USDT probe provider:name
+/// in binary
+///
+/// The argument specs of the sites are parsed and stored in the spec map of
+/// the program, each site gets the index of its spec as the BPF cookie.
+int attach_usdt_sites(const struct bpf_program *prog, pid_t pid,
+                      const char *binary, std::string_view provider,
+                      std::string_view name, struct bpf_link **link) {
+  std::string map_name = "__rex_usdt_specs_"s + bpf_program__name(prog);
+  std::unordered_map<std::string, uint32_t> spec_ids;
+  auto ulink = std::make_unique<usdt_link>();
+  int err;
+
+  ulink->link.detach = usdt_link::detach;
+  ulink->link.dealloc = usdt_link::dealloc;
+  ulink->link.fd = -1;
+
+  bpf_map *specs = bpf_object__find_map_by_name(prog->obj, map_name.c_str());
+  if (!specs) {
+    std::cerr << "usdt: map " << map_name << " not found" << std::endl;
+    return -ENOENT;
+  }
+
+  auto notes = usdt::read_notes(binary);
+  if (!notes)
+    return -EINVAL;
+
+  for (const auto &note : *notes) {
+    bpf_uprobe_opts opts{};
+
+    if (note.provider != provider || note.name != name)
+      continue;
+
+    // Sites sharing the same argument specs share the same spec entry
+    auto [it, inserted] = spec_ids.try_emplace(note.args, spec_ids.size());
+    if (inserted) {
+      auto spec = usdt::parse_args(note.args);
+      if (!spec) {
+        std::cerr << "usdt: unsupported argument spec \"" << note.args
+                  << "\" of " << provider << ":" << name << std::endl;
+        return -EINVAL;
+      }
+
+      if (it->second >= usdt::max_specs) {
+        std::cerr << "usdt: too many argument specs for " << provider << ":"
+                  << name << std::endl;
+        return -E2BIG;
+      }
+
+      err = bpf_map__update_elem(specs, &it->second, sizeof(it->second),
+                                 &*spec, sizeof(*spec), BPF_ANY);
+      if (err) {
+        std::cerr << "usdt: failed to update " << map_name << ": " << err
+                  << std::endl;
+        return err;
+      }
+    }
+
+    opts.sz = sizeof(opts);
+    opts.ref_ctr_offset = note.semaphore;
+    opts.bpf_cookie = it->second;
+
+    if (debug)
+      std::clog << "usdt: " << provider << ":" << name << " at 0x" << std::hex
+                << note.offset << std::dec << ", spec " << it->second
+                << std::endl;
+
+    bpf_link *uprobe = bpf_program__attach_uprobe_opts(prog, pid, binary,
+                                                        note.offset, &opts);
+    err = libbpf_get_error(uprobe);
+    if (err) {
+      usdt_link::detach(&ulink->link);
+      return err;
+    }
+
+    ulink->uprobes.push_back(uprobe);
+  }
+
+  if (ulink->uprobes.empty()) {
+    std::cerr << "usdt: " << provider << ":" << name << " not found in "
+              << binary << std::endl;
+    return -ENOENT;
+  }
+
+  *link = &ulink.release()->link;
+  return 0;
+}
+
+/// @brief Splits "[<binary>:]<provider>:<name>" at the end of a USDT section
+std::optional<std::tuple<std::string, std::string, std::string>>
+parse_usdt_section(std::string_view sec_name) {
+  sec_name.remove_prefix(std::min(sec_name.size(), "rex/usdt/"sv.size()));
+
+  size_t name_colon = sec_name.rfind(':');
+  if (name_colon == sec_name.npos)
+    return std::nullopt;
+
+  std::string_view name = sec_name.substr(name_colon + 1);
+  sec_name.remove_suffix(sec_name.size() - name_colon);
+
+  size_t provider_colon = sec_name.rfind(':');
+  std::string_view provider = provider_colon == sec_name.npos
+                                  ? sec_name
+                                  : sec_name.substr(provider_colon + 1);
+  std::string_view binary = provider_colon == sec_name.npos
+                                ? ""sv
+                                : sec_name.substr(0, provider_colon);
+
+  if (provider.empty() || name.empty())
+    return std::nullopt;
+
+  return std::tuple(std::string(binary), std::string(provider),
+                    std::string(name));
+}
+
+/// @brief Attaches a "rex/usdt/<binary>:<provider>:<name>" program, programs
+/// without a binary have to be attached with rex_prog_attach_usdt
+int attach_usdt(const struct bpf_program *prog, long, struct bpf_link **link) {
+  auto parsed = parse_usdt_section(bpf_program__section_name(prog));
+  if (!parsed) {
+    std::cerr << "usdt: invalid section " << bpf_program__section_name(prog)
+              << std::endl;
+    return -EINVAL;
+  }
+
+  auto &[binary, provider, name] = *parsed;
+
+  // No auto-attach, libbpf turns the missing link into -EOPNOTSUPP
+  if (binary.empty()) {
+    *link = nullptr;
+    return 0;
+  }
+
+  return attach_usdt_sites(prog, -1, binary.c_str(), provider, name, link);
+}
+
+/// @brief Attaches a "rex/netfilter/<family>/<hook>/<priority>" program, the
+/// section is generated by #[rex_netfilter]
+int attach_netfilter(const struct bpf_program *prog, long,
+                     struct bpf_link **link) {
+  static constexpr std::pair<std::string_view, __u32> families[] = {
+      {"ipv4", NFPROTO_IPV4},
+      {"ipv6", NFPROTO_IPV6},
+  };
+  static constexpr std::pair<std::string_view, __u32> hooks[] = {
+      {"prerouting", NF_INET_PRE_ROUTING}, {"input", NF_INET_LOCAL_IN},
+      {"forward", NF_INET_FORWARD},        {"output", NF_INET_LOCAL_OUT},
+      {"postrouting", NF_INET_POST_ROUTING},
+  };
+
+  std::string_view sec_name = bpf_program__section_name(prog);
+  std::vector<std::string_view> parts;
+  bpf_netfilter_opts opts{};
+
+  for (auto part : std::views::split(
+           sec_name.substr("rex/netfilter/"sv.size()), '/'))
+    parts.emplace_back(part.begin(), part.end());
+
+  if (parts.size() != 3) {
+    std::cerr << "netfilter: invalid section " << sec_name << std::endl;
+    return -EINVAL;
+  }
+
+  auto family = std::ranges::find(families, parts[0],
+                                  &std::pair<std::string_view, __u32>::first);
+  auto hook = std::ranges::find(hooks, parts[1],
+                                &std::pair<std::string_view, __u32>::first);
+  int priority;
+  auto [end, ec] = std::from_chars(
+      parts[2].data(), parts[2].data() + parts[2].size(), priority);
+
+  if (family == std::end(families) || hook == std::end(hooks) ||
+      ec != std::errc() || end != parts[2].data() + parts[2].size()) {
+    std::cerr << "netfilter: invalid section " << sec_name << std::endl;
+    return -EINVAL;
+  }
+
+  opts.sz = sizeof(opts);
+  opts.pf = family->second;
+  opts.hooknum = hook->second;
+  opts.priority = priority;
+
+  *link = bpf_program__attach_netfilter(prog, &opts);
+  return libbpf_get_error(*link);
+}
+
+// Sections that libbpf cannot attach or does not know about, they are matched
+// before the libbpf section definitions. The attach functions still use the
+// libbpf APIs.
+const bpf_sec_def rex_section_defs[] = {
+    {
+        .sec = const_cast<char *>("rex/kprobe.multi/"),
+        .prog_type = BPF_PROG_TYPE_KPROBE,
+        .expected_attach_type = BPF_TRACE_KPROBE_MULTI,
+        .cookie = 0,
+        .prog_attach_fn = attach_kprobe_multi,
+    },
+    {
+        .sec = const_cast<char *>("rex/kretprobe.multi/"),
+        .prog_type = BPF_PROG_TYPE_KPROBE,
+        .expected_attach_type = BPF_TRACE_KPROBE_MULTI,
+        .cookie = 1,
+        .prog_attach_fn = attach_kprobe_multi,
+    },
+    {
+        .sec = const_cast<char *>("rex/netfilter/"),
+        .prog_type = BPF_PROG_TYPE_NETFILTER,
+        .expected_attach_type = BPF_NETFILTER,
+        .prog_attach_fn = attach_netfilter,
+    },
+    {
+        .sec = const_cast<char *>("rex/struct_ops/"),
+        .prog_type = BPF_PROG_TYPE_STRUCT_OPS,
+    },
+    {
+        .sec = const_cast<char *>("rex/uprobe/"),
+        .prog_type = BPF_PROG_TYPE_KPROBE,
+        .cookie = 0,
+        .prog_attach_fn = attach_uprobe,
+    },
+    {
+        .sec = const_cast<char *>("rex/uretprobe/"),
+        .prog_type = BPF_PROG_TYPE_KPROBE,
+        .cookie = 1,
+        .prog_attach_fn = attach_uprobe,
+    },
+    {
+        .sec = const_cast<char *>("rex/usdt/"),
+        .prog_type = BPF_PROG_TYPE_KPROBE,
+        .prog_attach_fn = attach_usdt,
+    },
+    {
+        .sec = const_cast<char *>("rex/xdp.frags"),
+        .prog_type = BPF_PROG_TYPE_XDP,
+        .expected_attach_type = BPF_XDP,
+        .cookie = SEC_XDP_FRAGS,
+    },
+};
+
+/// @brief Walk through our own section definitions and then the static
+/// const struct bpf_sec_def section_defs in libbpf.c and figure out the
+/// valid bpf section
 ///
 /// @param sec_name section for our own rex prog
 /// @return section_defs
 const bpf_sec_def *find_sec_def(std::string_view sec_name) {
+  for (const auto &sec_def : rex_section_defs) {
+    if (sec_def_matches(&sec_def, sec_name))
+      return &sec_def;
+  }
+
   for (size_t i = 0; i < global_bpf_section_defs.size; i++) {
     if (!sec_def_matches(&global_bpf_section_defs.arr[i], sec_name))
       continue;
@@ -71,6 +516,20 @@ const bpf_sec_def *find_sec_def(std::string_view sec_name) {
   return nullptr;
 }
 
+/// @brief Computes the BPF_PROG_LOAD flags requested by a section
+///
+/// @param sec_def section definition of the program
+/// @return prog_flags to load the program with
+__u32 prog_load_flags(const bpf_sec_def *sec_def) {
+  // The cookie of our own attachable sections is passed to their attach
+  // functions and does not hold sec_def_flags
+  if (sec_def->prog_type == BPF_PROG_TYPE_XDP &&
+      sec_def->cookie & SEC_XDP_FRAGS)
+    return BPF_F_XDP_HAS_FRAGS;
+
+  return 0;
+}
+
 inline long bpf(__u64 cmd, union bpf_attr *attr, unsigned int size) {
   return syscall(__NR_bpf, cmd, attr, size);
 }
@@ -181,11 +640,17 @@ private:
   Elf64_Off offset;
   std::optional<int> prog_fd;
 
+  // Set for struct_ops programs, which are attached to a member of a kernel
+  // struct identified by its BTF type id and the member index
+  __u32 attach_btf_id;
+  enum bpf_attach_type expected_attach_type;
+
 public:
   rex_prog() = delete;
   rex_prog(const char *nm, const char *scn_nm, Elf64_Off off)
-      : name(nm), scn_name(scn_nm), offset(off) {
+      : name(nm), scn_name(scn_nm), offset(off), attach_btf_id(0) {
     sec_def = find_sec_def(scn_name);
+    expected_attach_type = sec_def->expected_attach_type;
   }
 
   rex_prog(const rex_prog &) = delete;
@@ -197,7 +662,8 @@ public:
   rex_prog(rex_prog &&other) noexcept
       : name(std::move(other.name)), scn_name(std::move(other.scn_name)),
         sec_def(std::move(other.sec_def)), offset(std::move(other.offset)),
-        prog_fd(std::move(other.prog_fd)) {
+        prog_fd(std::move(other.prog_fd)), attach_btf_id(other.attach_btf_id),
+        expected_attach_type(other.expected_attach_type) {
     other.sec_def = nullptr;
     other.offset = -1;
     other.prog_fd = std::nullopt;
@@ -225,6 +691,71 @@ public:
         .sec_def = sec_def,
         .fd = prog_fd.value(),
         .type = sec_def->prog_type,
+        .expected_attach_type = expected_attach_type,
+        .attach_btf_id = attach_btf_id,
+    };
+  }
+
+  friend struct ::rex_obj;
+};
+
+/// @brief An object in the .struct_ops section, registered as a
+/// BPF_MAP_TYPE_STRUCT_OPS map of the kernel struct it mirrors
+///
+/// The symbol of the object is "<struct>.<name>", e.g.
+/// "tcp_congestion_ops.reno". Its function pointers are relocated to the
+/// entry points of the programs implementing the callbacks.
+struct rex_struct_ops {
+private:
+  std::string type;
+  std::string name;
+  std::vector<unsigned char> data;
+  // Offset of a function pointer in data and the address it points to
+  std::vector<std::pair<size_t, Elf64_Addr>> entries;
+
+  // Filled in from the vmlinux BTF, the value is a
+  // struct bpf_struct_ops_<struct>, in which the function pointers are
+  // replaced with the fds of the programs
+  __u32 value_type_id;
+  std::vector<unsigned char> value;
+  std::vector<std::pair<size_t, size_t>> prog_slots;
+  std::optional<int> map_fd;
+
+public:
+  rex_struct_ops() = delete;
+  rex_struct_ops(std::string_view ty, std::string_view nm,
+                 const unsigned char *begin, size_t size)
+      : type(ty), name(nm), data(begin, begin + size), value_type_id(0) {}
+
+  rex_struct_ops(const rex_struct_ops &) = delete;
+  rex_struct_ops(rex_struct_ops &&) = delete;
+
+  ~rex_struct_ops() {
+    map_fd.transform([](int fd) { return close(fd); });
+  }
+
+  rex_struct_ops &operator=(const rex_struct_ops &) = delete;
+  rex_struct_ops &operator=(rex_struct_ops &&) = delete;
+
+  std::optional<bpf_map> bpfmap() {
+    // Do not create a bpf_map if the map has not been loaded
+    if (!map_fd)
+      return std::nullopt;
+
+    return bpf_map{
+        .name = name.data(),
+        .fd = map_fd.value(),
+        .inner_map_fd = -1,
+        .def =
+            {
+                .type = BPF_MAP_TYPE_STRUCT_OPS,
+                .key_size = sizeof(__u32),
+                .value_size = static_cast<unsigned int>(value.size()),
+                .max_entries = 1,
+                .map_flags = BPF_F_LINK,
+            },
+        .btf_vmlinux_value_type_id = value_type_id,
+        .libbpf_type = LIBBPF_MAP_UNSPEC,
     };
   }
 
@@ -248,6 +779,10 @@ private:
     }
   };
 
+  struct btf_del {
+    [[gnu::always_inline]] void operator()(btf *bp) const { btf__free(bp); }
+  };
+
   struct bpf_obj_del {
     [[gnu::always_inline]] void operator()(bpf_object *bp) const {
       delete[] bp->programs;
@@ -258,11 +793,13 @@ private:
 
   std::vector<rex_prog> progs;
   std::unordered_map<Elf64_Off, rex_map> map_defs;
+  std::unordered_map<Elf64_Addr, rex_struct_ops> struct_ops;
 
   std::unique_ptr<Elf, elf_del> elf;
   Elf_Scn *symtab_scn;
   Elf_Scn *dynsym_scn;
   Elf_Scn *maps_scn;
+  Elf_Scn *struct_ops_scn;
 
   // Global Offset Table for PIE
   Elf_Scn *got_scn;
@@ -286,6 +823,10 @@ private:
   int parse_progs();
   int parse_got();
   int parse_rela_dyn();
+  int parse_struct_ops();
+  int resolve_struct_ops();
+  int resolve_lsm();
+  int create_struct_ops();
 
 public:
   rex_obj() = delete;
@@ -305,7 +846,7 @@ public:
 
 rex_obj::rex_obj(const char *c_path)
     : map_defs(), symtab_scn(nullptr), dynsym_scn(nullptr), maps_scn(nullptr),
-      prog_fd(-1), loaded(false) {
+      struct_ops_scn(nullptr), prog_fd(-1), loaded(false) {
   struct stat st;
   void *mmap_ret;
   int fd = open(c_path, 0, O_RDONLY);
@@ -373,6 +914,8 @@ int rex_obj::parse_scns() {
       this->dynsym_scn = scn;
     else if (!strcmp(".maps", name))
       this->maps_scn = scn;
+    else if (!strcmp(".struct_ops", name))
+      this->struct_ops_scn = scn;
     else if (sh->sh_type == SHT_RELA && !strcmp(".rela.dyn", name))
       this->rela_dyn_scn = scn;
   }
@@ -588,6 +1131,278 @@ int rex_obj::parse_rela_dyn() {
   return 0;
 }
 
+int rex_obj::parse_struct_ops() {
+  Elf_Data *data, *syms, *relas;
+  Elf64_Shdr *sh;
+  size_t strtabidx;
+  int nr_syms, shndx;
+
+  if (!this->struct_ops_scn)
+    return 0;
+
+  sh = elf64_getshdr(struct_ops_scn);
+  data = elf_getdata(struct_ops_scn, 0);
+  syms = elf_getdata(symtab_scn, 0);
+
+  if (!data || !data->d_buf || !syms) {
+    std::cerr << "elf: failed to get struct_ops definitions" << std::endl;
+    return -1;
+  }
+
+  strtabidx = elf64_getshdr(symtab_scn)->sh_link;
+  shndx = elf_ndxscn(struct_ops_scn);
+  nr_syms = syms->d_size / sizeof(Elf64_Sym);
+
+  for (int i = 0; i < nr_syms; i++) {
+    Elf64_Sym *sym = reinterpret_cast<Elf64_Sym *>(syms->d_buf) + i;
+    const char *name;
+
+    if (sym->st_shndx != shndx || ELF64_ST_TYPE(sym->st_info) != STT_OBJECT)
+      continue;
+
+    name = elf_strptr(elf.get(), strtabidx, sym->st_name);
+    std::string_view sym_name = name ? name : "";
+    size_t dot = sym_name.find('.');
+    size_t off = sym->st_value - sh->sh_addr;
+
+    if (dot == sym_name.npos || !dot || dot == sym_name.size() - 1) {
+      std::cerr << "elf: invalid struct_ops symbol \"" << sym_name << "\""
+                << std::endl;
+      return -1;
+    }
+
+    if (sym->st_value < sh->sh_addr || off > data->d_size ||
+        sym->st_size > data->d_size - off) {
+      std::cerr << "elf: struct_ops " << sym_name << " out of section"
+                << std::endl;
+      return -1;
+    }
+
+    if (debug) {
+      std::clog << "struct_ops: " << sym_name << ", st_value=0x" << std::hex
+                << sym->st_value << std::dec << ", st_size=" << sym->st_size
+                << std::endl;
+    }
+
+    struct_ops.try_emplace(sym->st_value, sym_name.substr(0, dot),
+                           sym_name.substr(dot + 1),
+                           static_cast<unsigned char *>(data->d_buf) + off,
+                           sym->st_size);
+  }
+
+  if (struct_ops.empty() || !this->rela_dyn_scn)
+    return 0;
+
+  // The function pointers are filled in by relative relocations, whose
+  // addends are the addresses of the entry points
+  relas = elf_getdata(rela_dyn_scn, 0);
+  if (!relas) {
+    std::cerr << "elf: failed to get .rela.dyn data" << std::endl;
+    return -1;
+  }
+
+  for (size_t i = 0; i < relas->d_size / sizeof(Elf64_Rela); i++) {
+    Elf64_Rela *rela = reinterpret_cast<Elf64_Rela *>(relas->d_buf) + i;
+
+    if (ELF64_R_TYPE(rela->r_info) != R_X86_64_RELATIVE)
+      continue;
+
+    for (auto &[addr, st] : struct_ops) {
+      if (rela->r_offset >= addr && rela->r_offset - addr < st.data.size())
+        st.entries.emplace_back(rela->r_offset - addr, rela->r_addend);
+    }
+  }
+
+  return 0;
+}
+
+int rex_obj::resolve_struct_ops() {
+  if (struct_ops.empty())
+    return 0;
+
+  std::unique_ptr<btf, btf_del> vmlinux(btf__load_vmlinux_btf());
+  if (!vmlinux) {
+    perror("btf__load_vmlinux_btf");
+    return -1;
+  }
+
+  for (auto &[_, st] : struct_ops) {
+    std::string value_name = "bpf_struct_ops_" + st.type;
+    int type_id = btf__find_by_name_kind(vmlinux.get(), st.type.c_str(),
+                                         BTF_KIND_STRUCT);
+    int value_id = btf__find_by_name_kind(vmlinux.get(), value_name.c_str(),
+                                          BTF_KIND_STRUCT);
+    std::optional<size_t> data_off;
+    size_t nr_progs = 0;
+
+    if (type_id < 0 || value_id < 0) {
+      std::cerr << "struct_ops: struct " << st.type
+                << " is not found in vmlinux BTF" << std::endl;
+      return -1;
+    }
+
+    const btf_type *type = btf__type_by_id(vmlinux.get(), type_id);
+    const btf_type *value_type = btf__type_by_id(vmlinux.get(), value_id);
+
+    // The Rex definition of the struct has to follow the kernel layout
+    if (type->size != st.data.size()) {
+      std::cerr << "struct_ops: size of " << st.name
+                << " does not match struct " << st.type << std::endl;
+      return -1;
+    }
+
+    for (__u16 i = 0; i < btf_vlen(value_type); i++) {
+      const char *name =
+          btf__name_by_offset(vmlinux.get(), btf_members(value_type)[i].name_off);
+      if (name && "data"sv == name)
+        data_off = btf_member_bit_offset(value_type, i) / 8;
+    }
+
+    if (!data_off) {
+      std::cerr << "struct_ops: unexpected layout of " << value_name
+                << std::endl;
+      return -1;
+    }
+
+    st.value_type_id = value_id;
+    st.value.assign(value_type->size, 0);
+
+    for (__u16 i = 0; i < btf_vlen(type); i++) {
+      const btf_member *member = btf_members(type) + i;
+      const btf_type *mtype = btf__type_by_id(
+          vmlinux.get(), btf__resolve_type(vmlinux.get(), member->type));
+      size_t off = btf_member_bit_offset(type, i) / 8;
+      auto entry = std::ranges::find(st.entries, off,
+                                     &std::pair<size_t, Elf64_Addr>::first);
+
+      if (entry == st.entries.end()) {
+        // Data members are copied as is, the kernel checks them
+        __s64 size = btf__resolve_size(vmlinux.get(), member->type);
+        if (!mtype || btf_is_ptr(mtype) || size <= 0)
+          continue;
+
+        if (off + size > st.data.size()) {
+          std::cerr << "struct_ops: unexpected layout of " << st.type
+                    << std::endl;
+          return -1;
+        }
+
+        memcpy(&st.value[*data_off + off], &st.data[off], size);
+        continue;
+      }
+
+      auto prog = std::ranges::find_if(progs, [&](const rex_prog &prog) {
+        return prog.offset == entry->second &&
+               prog.sec_def->prog_type == BPF_PROG_TYPE_STRUCT_OPS;
+      });
+
+      if (!mtype || !btf_is_ptr(mtype) || prog == progs.end()) {
+        std::cerr << "struct_ops: member " << i << " of " << st.name
+                  << " is not a struct_ops program" << std::endl;
+        return -1;
+      }
+
+      prog->attach_btf_id = type_id;
+      prog->expected_attach_type = static_cast<enum bpf_attach_type>(i);
+      st.prog_slots.emplace_back(*data_off + off, prog - progs.begin());
+      nr_progs++;
+    }
+
+    if (nr_progs != st.entries.size()) {
+      std::cerr << "struct_ops: " << st.name
+                << " has pointers that are not callbacks" << std::endl;
+      return -1;
+    }
+  }
+
+  return 0;
+}
+
+int rex_obj::resolve_lsm() {
+  std::unique_ptr<btf, btf_del> vmlinux;
+
+  for (auto &prog : progs) {
+    if (prog.expected_attach_type != BPF_LSM_MAC)
+      continue;
+
+    if (!vmlinux) {
+      vmlinux.reset(btf__load_vmlinux_btf());
+      if (!vmlinux) {
+        perror("btf__load_vmlinux_btf");
+        return -1;
+      }
+    }
+
+    // "rex/lsm/<hook>" attaches to the bpf_lsm_<hook> function
+    std::string_view hook = prog.scn_name;
+    hook.remove_prefix(hook.find('/', "rex/"sv.size()) + 1);
+    std::string func = "bpf_lsm_" + std::string(hook);
+    int type_id =
+        btf__find_by_name_kind(vmlinux.get(), func.c_str(), BTF_KIND_FUNC);
+
+    if (type_id < 0) {
+      std::cerr << "lsm: hook " << hook << " is not found in vmlinux BTF"
+                << std::endl;
+      return -1;
+    }
+
+    prog.attach_btf_id = type_id;
+  }
+
+  return 0;
+}
+
+int rex_obj::create_struct_ops() {
+  for (auto &[_, st] : struct_ops) {
+    __u32 key = 0;
+    int ret;
+
+    // The kernel takes the fd of the program in place of the function
+    // pointer
+    for (const auto &[off, idx] : st.prog_slots) {
+      uint64_t fd = progs[idx].prog_fd.value();
+      memcpy(&st.value[off], &fd, sizeof(fd));
+    }
+
+    union bpf_attr attr{
+        .map_type = BPF_MAP_TYPE_STRUCT_OPS,
+        .key_size = sizeof(key),
+        .value_size = static_cast<__u32>(st.value.size()),
+        .max_entries = 1,
+        .map_flags = BPF_F_LINK,
+        .btf_vmlinux_value_type_id = st.value_type_id,
+    };
+
+    memcpy(attr.map_name, st.name.c_str(),
+           std::min(st.name.size(), sizeof(attr.map_name) - 1));
+
+    ret = bpf(BPF_MAP_CREATE, &attr, sizeof(attr));
+    if (ret < 0) {
+      perror("bpf_map_create");
+      return -1;
+    }
+
+    st.map_fd = ret;
+
+    // The ops are registered once the map is attached
+    attr = {};
+    attr.map_fd = ret;
+    attr.key = reinterpret_cast<__u64>(&key);
+    attr.value = reinterpret_cast<__u64>(st.value.data());
+
+    if (bpf(BPF_MAP_UPDATE_ELEM, &attr, sizeof(attr)) < 0) {
+      perror("bpf_map_update_elem");
+      return -1;
+    }
+
+    if (debug)
+      std::clog << "struct_ops " << st.name << " created, fd = " << ret
+                << std::endl;
+  }
+
+  return 0;
+}
+
 int rex_obj::parse_elf() {
   int ret;
 
@@ -600,6 +1415,7 @@ int rex_obj::parse_elf() {
   ret = ret < 0 ? ret : this->parse_maps();
   ret = ret < 0 ? ret : this->parse_progs();
   ret = ret < 0 ? ret : this->parse_rela_dyn();
+  ret = ret < 0 ? ret : this->parse_struct_ops();
 
   return ret;
 }
@@ -707,9 +1523,20 @@ int rex_obj::load() {
     goto close_fds;
   }
 
+  // struct_ops programs need the kernel struct and the member they implement
+  if (resolve_struct_ops() < 0)
+    goto close_fds;
+
+  // LSM programs need the BTF id of the hook they attach to
+  if (resolve_lsm() < 0)
+    goto close_fds;
+
   for (auto &prog : progs) {
     int curr_fd;
     attr.prog_type = prog.sec_def->prog_type;
+    attr.expected_attach_type = prog.expected_attach_type;
+    attr.attach_btf_id = prog.attach_btf_id;
+    attr.prog_flags = prog_load_flags(prog.sec_def);
     strncpy(attr.prog_name, prog.name.c_str(), sizeof(attr.prog_name) - 1);
     attr.base_prog_fd = this->prog_fd.value();
     attr.prog_offset = prog.offset;
@@ -729,6 +1556,9 @@ int rex_obj::load() {
                 << " loaded, fd = " << prog.prog_fd.value_or(-1) << std::endl;
   }
 
+  if (create_struct_ops() < 0)
+    goto close_fds;
+
   loaded = true;
   return ret;
 
@@ -757,8 +1587,8 @@ bpf_object *rex_obj::bpf_obj() {
     return bpf_obj_ptr.get();
 
   // Create a new ptr
-  decltype(bpf_obj_ptr) ptr(new bpf_object, bpf_obj_del());
-  ptr->maps = new bpf_map[map_defs.size()];
+  decltype(bpf_obj_ptr) ptr(new bpf_object{}, bpf_obj_del());
+  ptr->maps = new bpf_map[map_defs.size() + struct_ops.size()];
   ptr->programs = new bpf_program[progs.size()];
 
   // Fill in maps
@@ -771,6 +1601,14 @@ bpf_object *rex_obj::bpf_obj() {
       return nullptr;
     }
   }
+  for (auto &[_, st] : struct_ops) {
+    if (std::optional<bpf_map> map = st.bpfmap()) {
+      ptr->maps[i] = std::move(map.value());
+      ptr->maps[i++].obj = ptr.get();
+    } else {
+      return nullptr;
+    }
+  }
   ptr->nr_maps = i;
 
   // Fill in programs
@@ -833,3 +1671,65 @@ rex_obj_get_bpf(rex_obj *obj) {
     return nullptr;
   }
 }
+
+[[nodiscard, gnu::visibility("default")]] bpf_link *
+rex_prog_attach_usdt(const bpf_program *prog, int pid,
+                     const char *binary_path) {
+  bpf_link *link = nullptr;
+  int err;
+
+  auto parsed = parse_usdt_section(bpf_program__section_name(prog));
+  if (!parsed) {
+    errno = EINVAL;
+    return nullptr;
+  }
+
+  auto &[_, provider, name] = *parsed;
+  try {
+    err = attach_usdt_sites(prog, pid, binary_path, provider, name, &link);
+  } catch (std::exception &e) {
+    std::cerr << e.what() << std::endl;
+    err = -ENOMEM;
+  }
+
+  if (err) {
+    errno = -err;
+    return nullptr;
+  }
+
+  return link;
+}
+
+[[nodiscard, gnu::visibility("default")]] bpf_link *
+rex_map_attach_struct_ops(const bpf_map *map) {
+  union bpf_attr attr = {};
+  int fd;
+
+  if (bpf_map__type(map) != BPF_MAP_TYPE_STRUCT_OPS) {
+    errno = EINVAL;
+    return nullptr;
+  }
+
+  attr.link_create.map_fd = bpf_map__fd(map);
+  attr.link_create.attach_type = BPF_STRUCT_OPS;
+
+  fd = bpf(BPF_LINK_CREATE, &attr, sizeof(attr));
+  if (fd < 0)
+    return nullptr;
+
+  // Closing the link unregisters the ops
+  auto *link = new (std::nothrow) bpf_link{
+      .detach = [](bpf_link *link) { return close(link->fd) ? -errno : 0; },
+      .dealloc = [](bpf_link *link) { delete link; },
+      .pin_path = nullptr,
+      .fd = fd,
+      .disconnected = false,
+  };
+
+  if (!link) {
+    close(fd);
+    errno = ENOMEM;
+  }
+
+  return link;
+}
@@ -0,0 +1,329 @@
+#include <fcntl.h>
+#include <libelf.h>
+#include <unistd.h>
+
+#include <charconv>
+#include <cstring>
+#include <iostream>
+#include <memory>
+#include <ranges>
+#include <utility>
+
+#include "usdt.h"
+
+using namespace std::literals;
+
+namespace usdt {
+
+namespace {
+
+// Ref: https://sourceware.org/systemtap/wiki/UserSpaceProbeImplementation
+constexpr uint32_t NT_STAPSDT = 3;
+constexpr auto stapsdt_note_scn = ".note.stapsdt"sv;
+constexpr auto stapsdt_base_scn = ".stapsdt.base"sv;
+
+struct elf_del {
+  void operator()(Elf *ep) const { elf_end(ep); }
+};
+
+struct fd_guard {
+  int fd;
+  ~fd_guard() { close(fd); }
+};
+
+/// @brief Translates a virtual address into a file offset based on the
+/// section that contains it
+std::optional<unsigned long>
+addr_to_offset(const std::vector<Elf64_Shdr> &shdrs, unsigned long addr) {
+  for (const auto &sh : shdrs) {
+    if (!sh.sh_addr || sh.sh_type == SHT_NOBITS || addr < sh.sh_addr ||
+        addr - sh.sh_addr >= sh.sh_size)
+      continue;
+
+    unsigned long offset = addr - sh.sh_addr;
+    if (__builtin_add_overflow(offset, sh.sh_offset, &offset))
+      return std::nullopt;
+
+    return offset;
+  }
+
+  return std::nullopt;
+}
+
+/// @brief Reads the NUL-terminated string at off, advancing off past it
+std::optional<std::string_view> read_str(std::string_view desc, size_t &off) {
+  size_t end = desc.find('\0', off);
+  if (end == desc.npos)
+    return std::nullopt;
+
+  auto str = desc.substr(off, end - off);
+  off = end + 1;
+  return str;
+}
+
+} // namespace
+
+std::optional<int64_t> parse_int(std::string_view str) {
+  bool neg = str.starts_with('-');
+  int base = 10;
+  uint64_t val;
+
+  if (neg)
+    str.remove_prefix(1);
+
+  if (str.starts_with("0x")) {
+    str.remove_prefix(2);
+    base = 16;
+  }
+
+  auto [end, ec] =
+      std::from_chars(str.data(), str.data() + str.size(), val, base);
+  if (str.empty() || ec != std::errc() || end != str.data() + str.size() ||
+      val > static_cast<uint64_t>(INT64_MAX))
+    return std::nullopt;
+
+  return neg ? -static_cast<int64_t>(val) : static_cast<int64_t>(val);
+}
+
+std::optional<std::pair<x86_reg, uint8_t>> parse_reg(std::string_view reg) {
+  // The size of the argument is already encoded in the spec so it is fine to
+  // read the whole register for all names but the high byte ones
+  static constexpr struct {
+    x86_reg reg;
+    std::string_view names[5];
+  } regs[] = {
+      {x86_reg::rax, {"rax", "eax", "ax", "al", "ah"}},
+      {x86_reg::rbx, {"rbx", "ebx", "bx", "bl", "bh"}},
+      {x86_reg::rcx, {"rcx", "ecx", "cx", "cl", "ch"}},
+      {x86_reg::rdx, {"rdx", "edx", "dx", "dl", "dh"}},
+      {x86_reg::rsi, {"rsi", "esi", "si", "sil"}},
+      {x86_reg::rdi, {"rdi", "edi", "di", "dil"}},
+      {x86_reg::rbp, {"rbp", "ebp", "bp", "bpl"}},
+      {x86_reg::rsp, {"rsp", "esp", "sp", "spl"}},
+      {x86_reg::rip, {"rip"}},
+      {x86_reg::r8, {"r8", "r8d", "r8w", "r8b"}},
+      {x86_reg::r9, {"r9", "r9d", "r9w", "r9b"}},
+      {x86_reg::r10, {"r10", "r10d", "r10w", "r10b"}},
+      {x86_reg::r11, {"r11", "r11d", "r11w", "r11b"}},
+      {x86_reg::r12, {"r12", "r12d", "r12w", "r12b"}},
+      {x86_reg::r13, {"r13", "r13d", "r13w", "r13b"}},
+      {x86_reg::r14, {"r14", "r14d", "r14w", "r14b"}},
+      {x86_reg::r15, {"r15", "r15d", "r15w", "r15b"}},
+  };
+
+  for (const auto &r : regs) {
+    for (size_t j = 0; j < std::size(r.names); j++) {
+      if (!r.names[j].empty() && r.names[j] == reg)
+        return std::pair{r.reg, static_cast<uint8_t>(j == 4 ? 8 : 0)};
+    }
+  }
+
+  return std::nullopt;
+}
+
+std::optional<arg_spec> parse_arg_spec(std::string_view str) {
+  arg_spec spec{};
+
+  size_t at = str.find('@');
+  if (at == str.npos)
+    return std::nullopt;
+
+  auto size = parse_int(str.substr(0, at));
+  if (!size)
+    return std::nullopt;
+
+  spec.is_signed = *size < 0;
+  switch (spec.is_signed ? -*size : *size) {
+  case 1:
+  case 2:
+  case 4:
+  case 8:
+    spec.size = spec.is_signed ? -*size : *size;
+    break;
+  default:
+    return std::nullopt;
+  }
+
+  std::string_view loc = str.substr(at + 1);
+  std::optional<std::pair<x86_reg, uint8_t>> reg;
+
+  if (loc.starts_with('$')) {
+    auto imm = parse_int(loc.substr(1));
+    if (!imm)
+      return std::nullopt;
+
+    spec.loc = arg_loc::constant;
+    spec.val_off = *imm;
+    return spec;
+  }
+
+  if (loc.starts_with('%')) {
+    spec.loc = arg_loc::reg;
+    reg = parse_reg(loc.substr(1));
+  } else {
+    // "off(%reg)", the offset is optional
+    size_t paren = loc.find("(%");
+    if (paren == loc.npos || !loc.ends_with(')'))
+      return std::nullopt;
+
+    if (paren) {
+      auto off = parse_int(loc.substr(0, paren));
+      if (!off)
+        return std::nullopt;
+      spec.val_off = *off;
+    }
+
+    spec.loc = arg_loc::reg_deref;
+    reg = parse_reg(loc.substr(paren + 2, loc.size() - paren - 3));
+  }
+
+  if (!reg)
+    return std::nullopt;
+
+  std::tie(spec.reg, spec.reg_shift) = *reg;
+  return spec;
+}
+
+std::optional<spec> parse_args(std::string_view args) {
+  spec result{};
+
+  for (auto word : std::views::split(args, ' ')) {
+    std::string_view arg(word.begin(), word.end());
+    if (arg.empty())
+      continue;
+
+    if (result.arg_cnt == max_args)
+      return std::nullopt;
+
+    auto spec = parse_arg_spec(arg);
+    if (!spec)
+      return std::nullopt;
+
+    result.args[result.arg_cnt++] = *spec;
+  }
+
+  return result;
+}
+
+std::optional<std::vector<note>> read_notes(const char *path) {
+  std::vector<Elf64_Shdr> shdrs;
+  std::vector<note> notes;
+  Elf_Scn *notes_scn = nullptr;
+  unsigned long base_addr = 0;
+  size_t shstrndx;
+
+  int fd = open(path, O_RDONLY | O_CLOEXEC);
+  if (fd < 0) {
+    std::cerr << "usdt: failed to open " << path << ": " << strerror(errno)
+              << std::endl;
+    return std::nullopt;
+  }
+
+  fd_guard guard{fd};
+  std::unique_ptr<Elf, elf_del> elf(elf_begin(fd, ELF_C_READ_MMAP, nullptr));
+
+  if (!elf || elf_kind(elf.get()) != ELF_K_ELF ||
+      elf_getshdrstrndx(elf.get(), &shstrndx)) {
+    std::cerr << "usdt: " << path << " is not a valid ELF file" << std::endl;
+    return std::nullopt;
+  }
+
+  for (auto scn = elf_nextscn(elf.get(), nullptr); scn;
+       scn = elf_nextscn(elf.get(), scn)) {
+    Elf64_Shdr *sh = elf64_getshdr(scn);
+    if (!sh) {
+      std::cerr << "usdt: " << path << " is not a 64-bit ELF file"
+                << std::endl;
+      return std::nullopt;
+    }
+
+    const char *name = elf_strptr(elf.get(), shstrndx, sh->sh_name);
+    if (!name)
+      continue;
+
+    if (sh->sh_type == SHT_NOTE && name == stapsdt_note_scn)
+      notes_scn = scn;
+    else if (name == stapsdt_base_scn)
+      base_addr = sh->sh_addr;
+
+    shdrs.push_back(*sh);
+  }
+
+  if (!notes_scn)
+    return notes;
+
+  Elf_Data *data = elf_getdata(notes_scn, nullptr);
+  if (!data || !data->d_buf)
+    return std::nullopt;
+
+  std::string_view buf(static_cast<const char *>(data->d_buf), data->d_size);
+  auto align4 = [](size_t n) { return (n + 3) & ~size_t(3); };
+  size_t off = 0;
+
+  while (buf.size() - off >= sizeof(Elf64_Nhdr)) {
+    Elf64_Nhdr nhdr;
+    memcpy(&nhdr, buf.data() + off, sizeof(nhdr));
+
+    size_t name_off = off + sizeof(nhdr);
+    size_t desc_off = name_off + align4(nhdr.n_namesz);
+    off = desc_off + align4(nhdr.n_descsz);
+
+    if (off > buf.size()) {
+      std::cerr << "usdt: truncated note in " << path << std::endl;
+      return std::nullopt;
+    }
+
+    // The name includes the terminating NUL
+    if (nhdr.n_type != NT_STAPSDT ||
+        buf.substr(name_off, nhdr.n_namesz) != "stapsdt\0"sv)
+      continue;
+
+    // desc: pc, base, semaphore (all u64), followed by provider, name and
+    // args as NUL-terminated strings
+    std::string_view desc = buf.substr(desc_off, nhdr.n_descsz);
+    uint64_t addrs[3];
+    size_t str_off = sizeof(addrs);
+
+    if (desc.size() < sizeof(addrs)) {
+      std::cerr << "usdt: truncated note in " << path << std::endl;
+      return std::nullopt;
+    }
+    memcpy(addrs, desc.data(), sizeof(addrs));
+
+    auto provider = read_str(desc, str_off);
+    auto name = read_str(desc, str_off);
+    auto args = read_str(desc, str_off);
+    if (!provider || !name || !args) {
+      std::cerr << "usdt: malformed note in " << path << std::endl;
+      return std::nullopt;
+    }
+
+    // Prelink may move the binary, the difference between the recorded and
+    // the actual address of .stapsdt.base needs to be applied
+    auto [pc, base, sema] = addrs;
+    if (base_addr && base)
+      pc = pc + base_addr - base;
+
+    auto pc_off = addr_to_offset(shdrs, pc);
+    std::optional<unsigned long> sema_off = 0;
+    if (sema)
+      sema_off = addr_to_offset(shdrs, sema);
+    if (!pc_off || !sema_off) {
+      std::cerr << "usdt: bad address in " << *provider << ":" << *name
+                << std::endl;
+      return std::nullopt;
+    }
+
+    notes.push_back({
+        .offset = *pc_off,
+        .semaphore = *sema_off,
+        .provider = std::string(*provider),
+        .name = std::string(*name),
+        .args = std::string(*args),
+    });
+  }
+
+  return notes;
+}
+
+} // namespace usdt
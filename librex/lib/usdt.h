@@ -0,0 +1,92 @@
+#ifndef LIBREX_USDT_H
+#define LIBREX_USDT_H
+
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <string_view>
+#include <vector>
+
+namespace usdt {
+
+// Must match USDT_MAX_ARGS and USDT_MAX_SPECS in rex/src/usdt/usdt_impl.rs
+constexpr size_t max_args = 12;
+constexpr size_t max_specs = 256;
+
+// Must match the USDT_ARG_* constants in rex/src/usdt/usdt_impl.rs
+enum class arg_loc : uint8_t {
+  constant,  // "$imm"
+  reg,       // "%reg"
+  reg_deref, // "off(%reg)"
+};
+
+/// @brief The x86-64 registers an argument can live in, must match the
+/// USDT_REG_* constants in rex/src/usdt/usdt_impl.rs
+enum class x86_reg : uint8_t {
+  rax,
+  rbx,
+  rcx,
+  rdx,
+  rsi,
+  rdi,
+  rbp,
+  rsp,
+  rip,
+  r8,
+  r9,
+  r10,
+  r11,
+  r12,
+  r13,
+  r14,
+  r15,
+};
+
+/// @brief A parsed argument spec, same layout as UsdtArgSpec in
+/// rex/src/usdt/usdt_impl.rs
+struct arg_spec {
+  int64_t val_off;   // the constant or the offset from the register
+  arg_loc loc;
+  x86_reg reg;
+  uint8_t reg_shift; // 8 for the high byte registers, e.g. "ah"
+  uint8_t size;
+  uint8_t is_signed;
+};
+
+/// @brief The arguments of a probe site, same layout as rex::usdt::UsdtSpec
+/// in rex/src/usdt/usdt_impl.rs
+struct spec {
+  arg_spec args[max_args];
+  uint32_t arg_cnt;
+};
+
+/// @brief A probe site described by a .note.stapsdt entry
+struct note {
+  unsigned long offset;    // file offset of the probe site
+  unsigned long semaphore; // file offset of the semaphore, 0 if none
+  std::string provider;
+  std::string name;
+  std::string args; // e.g. "-4@%edi 8@-8(%rbp)"
+};
+
+std::optional<int64_t> parse_int(std::string_view str);
+
+/// @brief Maps a x86-64 register name to the register and the shift of the
+/// sub-register in it
+std::optional<std::pair<x86_reg, uint8_t>> parse_reg(std::string_view reg);
+
+/// @brief Parses a single argument spec, e.g. "-4@%edi", "8@-8(%rbp)" or
+/// "4@$5"
+std::optional<arg_spec> parse_arg_spec(std::string_view str);
+
+/// @brief Parses the space-separated argument specs of a probe site
+std::optional<spec> parse_args(std::string_view args);
+
+/// @brief Collects all probe sites from the .note.stapsdt section of the ELF
+/// file at path
+std::optional<std::vector<note>> read_notes(const char *path);
+
+} // namespace usdt
+
+#endif // LIBREX_USDT_H
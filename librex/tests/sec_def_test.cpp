@@ -0,0 +1,49 @@
+// The section lookup lives in an anonymous namespace, pull it in directly
+#include "../lib/librex.cpp"
+
+#include <cstdlib>
+#include <iostream>
+
+namespace {
+
+int failures = 0;
+
+#define CHECK(cond)                                                            \
+  do {                                                                         \
+    if (!(cond)) {                                                             \
+      std::cerr << __FILE__ << ":" << __LINE__ << ": " #cond << std::endl;     \
+      failures++;                                                              \
+    }                                                                          \
+  } while (0)
+
+void test_xdp_frags() {
+  const bpf_sec_def *sec_def = find_sec_def("rex/xdp.frags");
+
+  CHECK(sec_def);
+  if (!sec_def)
+    return;
+
+  CHECK(sec_def->prog_type == BPF_PROG_TYPE_XDP);
+  CHECK(sec_def->expected_attach_type == BPF_XDP);
+  CHECK(prog_load_flags(sec_def) == BPF_F_XDP_HAS_FRAGS);
+}
+
+void test_no_flags() {
+  for (auto sec_name : {"rex/xdp", "rex/uretprobe/bin:func",
+                        "rex/kretprobe.multi/func"}) {
+    const bpf_sec_def *sec_def = find_sec_def(sec_name);
+
+    CHECK(sec_def);
+    if (sec_def)
+      CHECK(prog_load_flags(sec_def) == 0);
+  }
+}
+
+} // end anonymous namespace
+
+int main() {
+  test_xdp_frags();
+  test_no_flags();
+
+  return failures ? EXIT_FAILURE : EXIT_SUCCESS;
+}
@@ -0,0 +1,167 @@
+#include <libelf.h>
+
+#include <algorithm>
+#include <cstdlib>
+#include <iostream>
+
+#include "usdt.h"
+
+using namespace std::literals;
+
+namespace {
+
+int failures = 0;
+
+#define CHECK(cond)                                                            \
+  do {                                                                         \
+    if (!(cond)) {                                                             \
+      std::cerr << __FILE__ << ":" << __LINE__ << ": " #cond << std::endl;     \
+      failures++;                                                              \
+    }                                                                          \
+  } while (0)
+
+bool spec_is(const std::optional<usdt::arg_spec> &spec, usdt::arg_loc loc,
+             usdt::x86_reg reg, uint8_t reg_shift, int64_t val_off,
+             uint8_t size, bool is_signed) {
+  return spec && spec->loc == loc && spec->reg == reg &&
+         spec->reg_shift == reg_shift && spec->val_off == val_off &&
+         spec->size == size && spec->is_signed == is_signed;
+}
+
+// Emits a .note.stapsdt entry the same way as STAP_PROBE2 of <sys/sdt.h>,
+// with the argument spec that gcc generates for memcached's
+// process__command probe
+[[gnu::noinline]] void probe_site() {
+  asm volatile("990: nop\n"
+               ".pushsection .note.stapsdt,\"?\",\"note\"\n"
+               ".balign 4\n"
+               ".4byte 992f-991f, 994f-993f, 3\n"
+               "991: .asciz \"stapsdt\"\n"
+               "992: .balign 4\n"
+               "993: .8byte 990b\n"
+               ".8byte _.stapsdt.base\n"
+               ".8byte 0\n"
+               ".asciz \"rex_test\"\n"
+               ".asciz \"process__command\"\n"
+               ".asciz \"8@%rbx -4@%r13d\"\n"
+               "994: .balign 4\n"
+               ".popsection\n"
+               ".ifndef _.stapsdt.base\n"
+               ".pushsection .stapsdt.base,\"aG\",\"progbits\","
+               ".stapsdt.base,comdat\n"
+               ".weak _.stapsdt.base\n"
+               ".hidden _.stapsdt.base\n"
+               "_.stapsdt.base: .space 1\n"
+               ".size _.stapsdt.base, 1\n"
+               ".popsection\n"
+               ".endif\n");
+}
+
+void test_parse_int() {
+  CHECK(usdt::parse_int("8") == 8);
+  CHECK(usdt::parse_int("-4") == -4);
+  CHECK(usdt::parse_int("0x10") == 0x10);
+  CHECK(usdt::parse_int("-0x1c") == -0x1c);
+  CHECK(!usdt::parse_int(""));
+  CHECK(!usdt::parse_int("-"));
+  CHECK(!usdt::parse_int("0x"));
+  CHECK(!usdt::parse_int("8a"));
+  CHECK(!usdt::parse_int("99999999999999999999"));
+}
+
+void test_parse_reg() {
+  using enum usdt::x86_reg;
+  using reg = std::pair<usdt::x86_reg, uint8_t>;
+
+  CHECK(usdt::parse_reg("rax") == reg(rax, 0));
+  CHECK(usdt::parse_reg("eax") == reg(rax, 0));
+  CHECK(usdt::parse_reg("al") == reg(rax, 0));
+  CHECK(usdt::parse_reg("ah") == reg(rax, 8));
+  CHECK(usdt::parse_reg("bh") == reg(rbx, 8));
+  CHECK(usdt::parse_reg("ch") == reg(rcx, 8));
+  CHECK(usdt::parse_reg("dh") == reg(rdx, 8));
+  CHECK(usdt::parse_reg("dil") == reg(rdi, 0));
+  CHECK(usdt::parse_reg("rip") == reg(rip, 0));
+  CHECK(usdt::parse_reg("r8b") == reg(r8, 0));
+  CHECK(usdt::parse_reg("r15d") == reg(r15, 0));
+  CHECK(!usdt::parse_reg(""));
+  CHECK(!usdt::parse_reg("sih"));
+  CHECK(!usdt::parse_reg("xmm0"));
+}
+
+void test_parse_arg_spec() {
+  using enum usdt::arg_loc;
+  using enum usdt::x86_reg;
+
+  // From the notes of glibc (setjmp, memory_mallopt) and libstdc++ (throw)
+  CHECK(spec_is(usdt::parse_arg_spec("8@%rdi"), reg, rdi, 0, 0, 8, false));
+  CHECK(spec_is(usdt::parse_arg_spec("-4@%esi"), reg, rsi, 0, 0, 4, true));
+  CHECK(spec_is(usdt::parse_arg_spec("-4@%r12d"), reg, r12, 0, 0, 4, true));
+  CHECK(spec_is(usdt::parse_arg_spec("8@%rax"), reg, rax, 0, 0, 8, false));
+  CHECK(spec_is(usdt::parse_arg_spec("-1@%ah"), reg, rax, 8, 0, 1, true));
+  CHECK(spec_is(usdt::parse_arg_spec("1@%dh"), reg, rdx, 8, 0, 1, false));
+  CHECK(spec_is(usdt::parse_arg_spec("8@-8(%rbp)"), reg_deref, rbp, 0, -8, 8,
+                false));
+  CHECK(spec_is(usdt::parse_arg_spec("-4@0x10(%rsp)"), reg_deref, rsp, 0, 0x10,
+                4, true));
+  CHECK(spec_is(usdt::parse_arg_spec("8@(%rdx)"), reg_deref, rdx, 0, 0, 8,
+                false));
+  CHECK(spec_is(usdt::parse_arg_spec("4@$5"), constant, rax, 0, 5, 4, false));
+  CHECK(spec_is(usdt::parse_arg_spec("-4@$-1"), constant, rax, 0, -1, 4, true));
+
+  CHECK(!usdt::parse_arg_spec("%rdi"));
+  CHECK(!usdt::parse_arg_spec("3@%rdi"));
+  CHECK(!usdt::parse_arg_spec("16@%xmm0"));
+  CHECK(!usdt::parse_arg_spec("8@-8(%rbp"));
+  CHECK(!usdt::parse_arg_spec("8@x(%rbp)"));
+  // SIB addressing is not supported
+  CHECK(!usdt::parse_arg_spec("8@-8(%rbp,%rax,8)"));
+}
+
+void test_parse_args() {
+  auto spec = usdt::parse_args("-4@%edi 8@-8(%rbp)  8@%rax");
+  CHECK(spec && spec->arg_cnt == 3);
+  CHECK(spec && spec->args[1].loc == usdt::arg_loc::reg_deref);
+
+  CHECK(usdt::parse_args("") && usdt::parse_args("")->arg_cnt == 0);
+  CHECK(!usdt::parse_args("8@%rdi 8@%xmm0"));
+  CHECK(!usdt::parse_args("1@$1 1@$1 1@$1 1@$1 1@$1 1@$1 1@$1 1@$1 1@$1 "
+                          "1@$1 1@$1 1@$1 1@$1"));
+}
+
+void test_read_notes(const char *self) {
+  auto notes = usdt::read_notes(self);
+  CHECK(notes);
+  if (!notes)
+    return;
+
+  auto note = std::ranges::find_if(*notes, [](const usdt::note &n) {
+    return n.provider == "rex_test" && n.name == "process__command";
+  });
+  CHECK(note != notes->end());
+  if (note == notes->end())
+    return;
+
+  CHECK(note->offset != 0);
+  CHECK(note->semaphore == 0);
+  CHECK(note->args == "8@%rbx -4@%r13d");
+
+  CHECK(!usdt::read_notes("/nonexistent"));
+}
+
+} // namespace
+
+int main(int, char *argv[]) {
+  if (elf_version(EV_CURRENT) == EV_NONE)
+    return EXIT_FAILURE;
+
+  probe_site();
+
+  test_parse_int();
+  test_parse_reg();
+  test_parse_arg_spec();
+  test_parse_args();
+  test_read_notes(argv[0]);
+
+  return failures ? EXIT_FAILURE : EXIT_SUCCESS;
+}
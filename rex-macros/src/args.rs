@@ -3,22 +3,106 @@ use std::collections::HashMap;
 use proc_macro2::TokenStream;
 use proc_macro_error::abort;
 use syn::spanned::Spanned;
-use syn::{parse_str, Expr, ExprAssign, Lit, LitStr, Result};
+use syn::{
+    parse_str, Expr, ExprArray, ExprAssign, ExprUnary, Lit, LitInt, LitStr,
+    Result, UnOp,
+};
+
+/// The value of a `key = value` macro argument
+pub(crate) enum ArgValue {
+    /// `key = "value"`
+    Str(LitStr),
+    /// `key = ["value1", "value2", ...]`
+    StrList(Vec<LitStr>),
+    /// `key = 42`
+    Int(LitInt),
+    /// `key`
+    Flag,
+}
 
 macro_rules! pop_string_args {
     ($self:expr, $key:expr) => {
-        $self.get($key).map(|v| v.value())
+        $self.get($key).map(|v| match v {
+            $crate::args::ArgValue::Str(s) => s.value(),
+            _ => proc_macro_error::abort_call_site!(
+                "`{}` expects a string literal",
+                $key
+            ),
+        })
+    };
+}
+
+macro_rules! pop_string_list_args {
+    ($self:expr, $key:expr) => {
+        $self.get($key).map(|v| match v {
+            $crate::args::ArgValue::StrList(l) => {
+                l.iter().map(|s| s.value()).collect::<Vec<_>>()
+            }
+            _ => proc_macro_error::abort_call_site!(
+                "`{}` expects an array of string literals",
+                $key
+            ),
+        })
+    };
+}
+
+macro_rules! pop_int_args {
+    ($self:expr, $key:expr, $ty:ty) => {
+        $self.get($key).map(|v| match v {
+            $crate::args::ArgValue::Int(i) => {
+                i.base10_parse::<$ty>().unwrap_or_else(|e| {
+                    proc_macro_error::abort!(i.span(), "{}", e)
+                })
+            }
+            _ => proc_macro_error::abort_call_site!(
+                "`{}` expects an integer literal",
+                $key
+            ),
+        })
+    };
+}
+
+macro_rules! pop_flag_args {
+    ($self:expr, $key:expr) => {
+        match $self.get($key) {
+            Some($crate::args::ArgValue::Flag) => true,
+            Some(_) => proc_macro_error::abort_call_site!(
+                "`{}` does not take a value",
+                $key
+            ),
+            None => false,
+        }
     };
 }
 
-pub(crate) fn parse_string_args(
+fn parse_str_lit(expr: Expr, input: &TokenStream) -> LitStr {
+    match expr {
+        Expr::Lit(syn::ExprLit {
+            lit: Lit::Str(lit_str),
+            ..
+        }) => lit_str,
+        _ => {
+            abort!(input.span(), "Macro processing failed, please follow the syntax `key` = `value`");
+        }
+    }
+}
+
+pub(crate) fn parse_args(
     input: TokenStream,
-) -> Result<HashMap<String, LitStr>> {
+) -> Result<HashMap<String, ArgValue>> {
     let parsed: syn::ExprArray = parse_str(&format!("[{input}]"))?;
     let mut map = HashMap::new();
 
     // Iterate over the expressions and extract key-value pairs
-    let parse_and_insert = |expr| {
+    let parse_and_insert = |expr: Expr| {
+        // `key` alone is a flag
+        if let Expr::Path(path) = &expr {
+            if let Some(ident) = path.path.get_ident() {
+                map.insert(ident.to_string(), ArgValue::Flag);
+            }
+            return;
+        }
+
         let Expr::Assign(ExprAssign { left, right, .. }) = expr else {
             return;
         };
@@ -29,13 +113,32 @@ pub(crate) fn parse_string_args(
 
         let key = ident.to_string();
         let value = match *right {
+            Expr::Array(ExprArray { elems, .. }) => ArgValue::StrList(
+                elems
+                    .into_iter()
+                    .map(|e| parse_str_lit(e, &input))
+                    .collect(),
+            ),
             Expr::Lit(syn::ExprLit {
-                lit: Lit::Str(lit_str),
+                lit: Lit::Int(lit_int),
                 ..
-            }) => lit_str,
-            _ => {
-                abort!(input.span(), "Macro processing failed, please follow the syntax `key` = `value`");
-            }
+            }) => ArgValue::Int(lit_int),
+            // `key = -42`
+            Expr::Unary(ExprUnary {
+                op: UnOp::Neg(_),
+                expr,
+                ..
+            }) => match *expr {
+                Expr::Lit(syn::ExprLit {
+                    lit: Lit::Int(lit_int),
+                    ..
+                }) => ArgValue::Int(LitInt::new(
+                    &format!("-{lit_int}"),
+                    lit_int.span(),
+                )),
+                expr => ArgValue::Str(parse_str_lit(expr, &input)),
+            },
+            expr => ArgValue::Str(parse_str_lit(expr, &input)),
         };
         map.insert(key, value);
     };
@@ -0,0 +1,61 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort_call_site;
+use quote::{format_ident, quote};
+use syn::{parse2, ItemFn, Result};
+
+use crate::args::parse_args;
+
+pub(crate) struct CgroupSkb {
+    direction: String,
+    item: ItemFn,
+}
+
+impl CgroupSkb {
+    // parse the argument of function
+    pub(crate) fn parse(
+        attrs: TokenStream,
+        item: TokenStream,
+    ) -> Result<CgroupSkb> {
+        let item: ItemFn = parse2(item)?;
+        let args = parse_args(attrs)?;
+
+        let direction = match pop_string_args!(args, "direction") {
+            Some(dir) if dir == "ingress" || dir == "egress" => dir,
+            Some(dir) => abort_call_site!(
+                "Invalid direction `{}`, expected `ingress` or `egress`",
+                dir
+            ),
+            None => abort_call_site!("`direction` is required"),
+        };
+
+        Ok(CgroupSkb { direction, item })
+    }
+
+    pub(crate) fn expand(&self) -> Result<TokenStream> {
+        let fn_name = self.item.sig.ident.clone();
+        let item = &self.item;
+        let function_name = format!("{fn_name}");
+        let prog_ident =
+            format_ident!("PROG_{}", fn_name.to_string().to_uppercase());
+        let attached_name = format!("rex/cgroup_skb/{}", self.direction);
+
+        let entry_name = format_ident!("__rex_entry_{}", fn_name);
+
+        let function_body_tokens = quote! {
+            #[inline(always)]
+            #item
+
+            #[used]
+            static #prog_ident: cgroup_skb =
+                unsafe { cgroup_skb::new(#fn_name) };
+
+            #[unsafe(export_name = #function_name)]
+            #[unsafe(link_section = #attached_name)]
+            extern "C" fn #entry_name(ctx: *mut ()) -> u32 {
+                use rex::prog_type::rex_prog;
+                #prog_ident.prog_run(ctx)
+            }
+        };
+        Ok(function_body_tokens)
+    }
+}
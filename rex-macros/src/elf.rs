@@ -0,0 +1,321 @@
+//! A minimal ELF64 (little-endian) reader used to resolve uprobe targets at
+//! compile time.
+//!
+//! Only the parts of the format needed to translate a symbol into a file
+//! offset are parsed, i.e. the section header table and the symbol tables.
+
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+const ELFMAG: &[u8; 4] = b"\x7fELF";
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+
+const SHT_SYMTAB: u32 = 2;
+const SHT_DYNSYM: u32 = 11;
+const SHT_NOBITS: u32 = 8;
+const SHN_UNDEF: u16 = 0;
+const STT_FUNC: u8 = 2;
+const STT_GNU_IFUNC: u8 = 10;
+
+const EHDR_SIZE: usize = 0x40;
+const SHDR_SIZE: usize = 64;
+const SYM_SIZE: usize = 24;
+
+fn invalid(msg: impl Into<String>) -> Error {
+    Error::new(ErrorKind::InvalidData, msg.into())
+}
+
+/// A section header, only fields needed by us are kept
+struct Section {
+    sh_type: u32,
+    addr: u64,
+    offset: u64,
+    size: u64,
+    link: u32,
+}
+
+pub(crate) struct Elf {
+    data: Vec<u8>,
+    sections: Vec<Section>,
+}
+
+impl Elf {
+    /// Reads and parses the ELF file at `path`
+    pub(crate) fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(fs::read(path)?)
+    }
+
+    fn parse(data: Vec<u8>) -> Result<Self> {
+        if data.len() < EHDR_SIZE || &data[..4] != ELFMAG {
+            return Err(invalid("not an ELF file"));
+        }
+        if data[4] != ELFCLASS64 || data[5] != ELFDATA2LSB {
+            return Err(invalid("only 64-bit little-endian ELF is supported"));
+        }
+
+        let mut elf = Self {
+            data,
+            sections: Vec::new(),
+        };
+
+        let shoff = elf.read_u64(0x28)? as usize;
+        let shentsize = elf.read_u16(0x3a)? as usize;
+        let shnum = elf.read_u16(0x3c)? as usize;
+
+        if shentsize != SHDR_SIZE {
+            return Err(invalid("unexpected section header size"));
+        }
+
+        elf.sections = (0..shnum)
+            .map(|i| {
+                let base = i
+                    .checked_mul(SHDR_SIZE)
+                    .and_then(|off| off.checked_add(shoff))
+                    .ok_or_else(|| invalid("bad section header offset"))?;
+                Ok(Section {
+                    sh_type: elf.read_u32(base + 4)?,
+                    addr: elf.read_u64(base + 16)?,
+                    offset: elf.read_u64(base + 24)?,
+                    size: elf.read_u64(base + 32)?,
+                    link: elf.read_u32(base + 40)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(elf)
+    }
+
+    fn bytes(&self, off: usize, len: usize) -> Result<&[u8]> {
+        off.checked_add(len)
+            .and_then(|end| self.data.get(off..end))
+            .ok_or_else(|| invalid("truncated ELF file"))
+    }
+
+    fn read_u16(&self, off: usize) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(off, 2)?.try_into().unwrap()))
+    }
+
+    fn read_u32(&self, off: usize) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes(off, 4)?.try_into().unwrap()))
+    }
+
+    fn read_u64(&self, off: usize) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes(off, 8)?.try_into().unwrap()))
+    }
+
+    /// Reads the NUL-terminated string at `off`
+    fn read_str(&self, off: usize) -> Result<&str> {
+        let tail = self.data.get(off..).ok_or_else(|| invalid("bad string"))?;
+        let len = tail
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| invalid("unterminated string"))?;
+        std::str::from_utf8(&tail[..len]).map_err(|e| invalid(e.to_string()))
+    }
+
+    fn strtab_str(&self, strtab: &Section, idx: u32) -> Result<&str> {
+        let off = (strtab.offset as usize)
+            .checked_add(idx as usize)
+            .ok_or_else(|| invalid("bad string offset"))?;
+        self.read_str(off)
+    }
+
+    /// Resolves the function `symbol` into its offset in the file, which is
+    /// what the kernel expects when attaching a uprobe.
+    ///
+    /// Both `.symtab` and `.dynsym` are searched, so stripped binaries still
+    /// work as long as the symbol is exported.
+    pub(crate) fn symbol_offset(&self, symbol: &str) -> Result<u64> {
+        for symtab in self
+            .sections
+            .iter()
+            .filter(|s| matches!(s.sh_type, SHT_SYMTAB | SHT_DYNSYM))
+        {
+            let strtab = self
+                .sections
+                .get(symtab.link as usize)
+                .ok_or_else(|| invalid("bad string table index"))?;
+
+            for i in 0..(symtab.size as usize / SYM_SIZE) {
+                let base = (symtab.offset as usize)
+                    .checked_add(i * SYM_SIZE)
+                    .ok_or_else(|| invalid("bad symbol table offset"))?;
+                let st_info = self.bytes(base + 4, 1)?[0];
+                let st_shndx = self.read_u16(base + 6)?;
+
+                if st_shndx == SHN_UNDEF ||
+                    !matches!(st_info & 0xf, STT_FUNC | STT_GNU_IFUNC) ||
+                    self.strtab_str(strtab, self.read_u32(base)?)? != symbol
+                {
+                    continue;
+                }
+
+                let st_value = self.read_u64(base + 8)?;
+                let section = self
+                    .sections
+                    .get(st_shndx as usize)
+                    .filter(|s| s.sh_type != SHT_NOBITS)
+                    .ok_or_else(|| invalid("bad symbol section index"))?;
+
+                return st_value
+                    .checked_sub(section.addr)
+                    .filter(|delta| *delta < section.size)
+                    .and_then(|delta| delta.checked_add(section.offset))
+                    .ok_or_else(|| {
+                        invalid(format!(
+                            "symbol `{symbol}` lies outside of its section"
+                        ))
+                    });
+            }
+        }
+
+        Err(Error::new(
+            ErrorKind::NotFound,
+            format!("symbol `{symbol}` not found"),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT_ADDR: u64 = 0x401000;
+    const TEXT_OFF: u64 = 0x200;
+    const SYMTAB_OFF: usize = 0x300;
+    const STRTAB: &[u8] = b"\0main\0data\0undef\0";
+
+    fn put_u16(data: &mut [u8], off: usize, val: u16) {
+        data[off..off + 2].copy_from_slice(&val.to_le_bytes());
+    }
+
+    fn put_u32(data: &mut [u8], off: usize, val: u32) {
+        data[off..off + 4].copy_from_slice(&val.to_le_bytes());
+    }
+
+    fn put_u64(data: &mut [u8], off: usize, val: u64) {
+        data[off..off + 8].copy_from_slice(&val.to_le_bytes());
+    }
+
+    // (name, info, shndx, value)
+    type Sym = (u32, u8, u16, u64);
+
+    // Lays out a file with a .text section at `text_addr` and a symbol
+    // table holding `syms`, whose names are taken from STRTAB
+    fn build(syms: &[Sym], text_addr: u64) -> Vec<u8> {
+        let strtab_off = SYMTAB_OFF + (syms.len() + 1) * SYM_SIZE;
+        let shoff = (strtab_off + STRTAB.len() + 7) & !7;
+        let mut data = vec![0u8; shoff + 4 * SHDR_SIZE];
+
+        data[..4].copy_from_slice(ELFMAG);
+        data[4] = ELFCLASS64;
+        data[5] = ELFDATA2LSB;
+        put_u64(&mut data, 0x28, shoff as u64);
+        put_u16(&mut data, 0x3a, SHDR_SIZE as u16);
+        put_u16(&mut data, 0x3c, 4);
+
+        // Index 0 is the null symbol
+        for (i, (name, info, shndx, value)) in syms.iter().enumerate() {
+            let base = SYMTAB_OFF + (i + 1) * SYM_SIZE;
+            put_u32(&mut data, base, *name);
+            data[base + 4] = *info;
+            put_u16(&mut data, base + 6, *shndx);
+            put_u64(&mut data, base + 8, *value);
+        }
+        data[strtab_off..strtab_off + STRTAB.len()].copy_from_slice(STRTAB);
+
+        // (type, addr, offset, size, link), index 0 is the null section
+        let sections = [
+            (1, text_addr, TEXT_OFF, 0x100, 0),
+            (
+                SHT_SYMTAB,
+                0,
+                SYMTAB_OFF as u64,
+                ((syms.len() + 1) * SYM_SIZE) as u64,
+                3,
+            ),
+            (3, 0, strtab_off as u64, STRTAB.len() as u64, 0),
+        ];
+        for (i, (sh_type, addr, offset, size, link)) in
+            sections.into_iter().enumerate()
+        {
+            let base = shoff + (i + 1) * SHDR_SIZE;
+            put_u32(&mut data, base + 4, sh_type);
+            put_u64(&mut data, base + 16, addr);
+            put_u64(&mut data, base + 24, offset);
+            put_u64(&mut data, base + 32, size);
+            put_u32(&mut data, base + 40, link);
+        }
+
+        data
+    }
+
+    const MAIN: Sym = (1, STT_FUNC, 1, TEXT_ADDR + 0x10);
+    const DATA: Sym = (6, 1, 1, TEXT_ADDR + 0x20);
+    const UNDEF: Sym = (11, STT_FUNC, SHN_UNDEF, 0);
+
+    #[test]
+    fn rejects_non_elf() {
+        assert!(Elf::parse(vec![0; EHDR_SIZE]).is_err());
+        assert!(Elf::parse(b"\x7fELF".to_vec()).is_err());
+
+        let mut data = build(&[], TEXT_ADDR);
+        data[4] = 1; // ELFCLASS32
+        assert!(Elf::parse(data).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_section_headers() {
+        let mut data = build(&[], TEXT_ADDR);
+        data.truncate(data.len() - SHDR_SIZE / 2);
+        assert!(Elf::parse(data).is_err());
+
+        let mut data = build(&[], TEXT_ADDR);
+        put_u64(&mut data, 0x28, u64::MAX - 8);
+        assert!(Elf::parse(data).is_err());
+    }
+
+    #[test]
+    fn resolves_function_symbols() {
+        let elf = Elf::parse(build(&[DATA, MAIN], TEXT_ADDR)).unwrap();
+        assert_eq!(elf.symbol_offset("main").unwrap(), TEXT_OFF + 0x10);
+    }
+
+    #[test]
+    fn skips_undefined_and_non_function_symbols() {
+        let elf = Elf::parse(build(&[DATA, UNDEF], TEXT_ADDR)).unwrap();
+        for symbol in ["data", "undef", "missing"] {
+            let err = elf.symbol_offset(symbol).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::NotFound);
+        }
+    }
+
+    #[test]
+    fn rejects_symbol_outside_of_its_section() {
+        // Below the section address, must not underflow
+        let sym = (1, STT_FUNC, 1, TEXT_ADDR - 1);
+        let elf = Elf::parse(build(&[sym], TEXT_ADDR)).unwrap();
+        let err = elf.symbol_offset("main").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let sym = (1, STT_FUNC, 1, TEXT_ADDR + 0x100);
+        let elf = Elf::parse(build(&[sym], TEXT_ADDR)).unwrap();
+        assert!(elf.symbol_offset("main").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_section_index() {
+        let sym = (1, STT_FUNC, 42, TEXT_ADDR);
+        let elf = Elf::parse(build(&[sym], TEXT_ADDR)).unwrap();
+        assert!(elf.symbol_offset("main").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_string_offset() {
+        let sym = (u32::MAX, STT_FUNC, 1, TEXT_ADDR);
+        let elf = Elf::parse(build(&[sym], TEXT_ADDR)).unwrap();
+        assert!(elf.symbol_offset("main").is_err());
+    }
+}
@@ -0,0 +1,45 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{ItemFn, Result};
+
+pub(crate) struct FlowDissector {
+    item: ItemFn,
+}
+
+impl FlowDissector {
+    // parse the argument of function
+    pub(crate) fn parse(
+        _: TokenStream,
+        item: TokenStream,
+    ) -> Result<FlowDissector> {
+        let item = syn::parse2(item)?;
+        Ok(FlowDissector { item })
+    }
+
+    pub(crate) fn expand(&self) -> Result<TokenStream> {
+        let fn_name = self.item.sig.ident.clone();
+        let item = &self.item;
+        let function_name = format!("{fn_name}");
+        let prog_ident =
+            format_ident!("PROG_{}", fn_name.to_string().to_uppercase());
+
+        let entry_name = format_ident!("__rex_entry_{}", fn_name);
+
+        let function_body_tokens = quote! {
+            #[inline(always)]
+            #item
+
+            #[used]
+            static #prog_ident: flow_dissector =
+                unsafe { flow_dissector::new(#fn_name) };
+
+            #[unsafe(export_name = #function_name)]
+            #[unsafe(link_section = "rex/flow_dissector")]
+            extern "C" fn #entry_name(ctx: *mut ()) -> u32 {
+                use rex::prog_type::rex_prog;
+                #prog_ident.prog_run(ctx)
+            }
+        };
+        Ok(function_body_tokens)
+    }
+}
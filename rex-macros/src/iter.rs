@@ -0,0 +1,89 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort_call_site;
+use quote::{format_ident, quote, ToTokens};
+use syn::{parse2, FnArg, ItemFn, Result, Type, TypePath};
+
+use crate::args::parse_args;
+
+pub(crate) struct Iter {
+    target: String,
+    item: ItemFn,
+}
+
+impl Iter {
+    // parse the argument of function
+    pub(crate) fn parse(attrs: TokenStream, item: TokenStream) -> Result<Iter> {
+        let item: ItemFn = parse2(item)?;
+        let args = parse_args(attrs)?;
+
+        let Some(target) = pop_string_args!(args, "target") else {
+            abort_call_site!("`target` is required");
+        };
+
+        Ok(Iter { target, item })
+    }
+
+    pub(crate) fn expand(&self) -> Result<TokenStream> {
+        let fn_name = self.item.sig.ident.clone();
+
+        // get context type
+        let FnArg::Typed(context_arg) =
+            self.item.sig.inputs.last().unwrap().clone()
+        else {
+            abort_call_site!("Program needs non-self arguments");
+        };
+        let Type::Reference(context_type_ref) = *context_arg.ty else {
+            abort_call_site!("Context type needs to be behind a reference");
+        };
+        let Type::Path(TypePath { path, .. }) = *context_type_ref.elem else {
+            abort_call_site!(
+                "Iterator context needs to be a literal type or a path to such"
+            );
+        };
+        let context_type = path.segments.last().unwrap().ident.to_string();
+
+        // Each target visits a different kind of object, make sure the context
+        // of the program matches the target it is attached to
+        let expected = match self.target.as_str() {
+            "task" => "TaskIterCtx",
+            "bpf_map_elem" => "BpfMapElemIterCtx",
+            "tcp" => "TcpIterCtx",
+            _ => abort_call_site!("Unsupported iterator target `{}`. If your needed target isn't supported consider opening a PR!", self.target),
+        };
+        if context_type != expected {
+            abort_call_site!(
+                "Iterator target `{}` expects `{}` as context, found `{}`",
+                self.target,
+                expected,
+                context_type
+            );
+        }
+        let full_context_type = path.to_token_stream();
+
+        let item = &self.item;
+        let function_name = format!("{fn_name}");
+        let prog_ident =
+            format_ident!("PROG_{}", fn_name.to_string().to_uppercase());
+        let attached_name = format!("rex/iter/{}", self.target);
+
+        let entry_name = format_ident!("__rex_entry_{}", fn_name);
+
+        let function_body_tokens = quote! {
+            #[inline(always)]
+            #item
+
+            #[used]
+            static #prog_ident: iter<#full_context_type> =
+               unsafe { iter::new(#fn_name) };
+
+            #[unsafe(export_name = #function_name)]
+            #[unsafe(link_section = #attached_name)]
+            extern "C" fn #entry_name(ctx: *mut ()) -> u32 {
+                use rex::prog_type::rex_prog;
+                #prog_ident.prog_run(ctx)
+            }
+        };
+
+        Ok(function_body_tokens)
+    }
+}
@@ -1,10 +1,13 @@
 use std::fmt;
+use std::path::Path;
 
 use proc_macro2::TokenStream;
+use proc_macro_error::abort_call_site;
 use quote::{format_ident, quote};
 use syn::{parse2, ItemFn, Result};
 
-use crate::args::parse_string_args;
+use crate::args::parse_args;
+use crate::elf::Elf;
 
 #[allow(dead_code)]
 pub enum KprobeFlavor {
@@ -27,6 +30,11 @@ impl fmt::Display for KprobeFlavor {
 
 pub(crate) struct KProbe {
     function: Option<String>,
+    functions: Option<Vec<String>>,
+    binary: Option<String>,
+    symbol: Option<String>,
+    offset: Option<u64>,
+    pid: Option<u32>,
     item: ItemFn,
 }
 
@@ -37,11 +45,118 @@ impl KProbe {
         item: TokenStream,
     ) -> Result<KProbe> {
         let item: ItemFn = parse2(item)?;
-        let args = parse_string_args(attrs)?;
+        let args = parse_args(attrs)?;
 
         let function = pop_string_args!(args, "function");
+        let functions = pop_string_list_args!(args, "functions");
+        let binary = pop_string_args!(args, "binary");
+        let symbol = pop_string_args!(args, "symbol");
+        let offset = pop_int_args!(args, "offset", u64);
+        let pid = pop_int_args!(args, "pid", u32);
 
-        Ok(KProbe { function, item })
+        if function.is_some() && functions.is_some() {
+            abort_call_site!(
+                "`function` and `functions` cannot be used together"
+            );
+        }
+
+        if binary.is_none() &&
+            (symbol.is_some() || offset.is_some() || pid.is_some())
+        {
+            abort_call_site!("`symbol`, `offset` and `pid` require `binary`");
+        }
+
+        // ':' and '@' separate the binary from the target and the pid in the
+        // section name, '+' separates the symbol from the offset
+        if let Some(binary) = &binary {
+            if binary.is_empty() || binary.contains([':', '@']) {
+                abort_call_site!("Invalid binary path `{}`", binary);
+            }
+        }
+
+        if let Some(symbol) = &symbol {
+            if symbol.is_empty() || symbol.contains([':', '+']) {
+                abort_call_site!("Invalid symbol `{}`", symbol);
+            }
+        }
+
+        if binary.is_some() && (function.is_some() || functions.is_some()) {
+            abort_call_site!(
+                "Use `symbol` instead of `function` together with `binary`"
+            );
+        }
+
+        if let Some(patterns) = &functions {
+            if patterns.is_empty() {
+                abort_call_site!("`functions` needs at least one pattern");
+            }
+
+            // Patterns are limited to the characters of kernel symbols plus
+            // the glob wildcards, which makes ',' a safe separator for the
+            // pattern list in the section name
+            let is_valid = |c: char| {
+                c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '*' | '?')
+            };
+            if let Some(p) = patterns
+                .iter()
+                .find(|p| p.is_empty() || !p.chars().all(is_valid))
+            {
+                abort_call_site!("Invalid function pattern `{}`", p);
+            }
+        }
+
+        Ok(KProbe {
+            function,
+            functions,
+            binary,
+            symbol,
+            offset,
+            pid,
+            item,
+        })
+    }
+
+    // Whether the binary can be read at compile time
+    fn is_local(binary: &str) -> bool {
+        let path = Path::new(binary);
+        path.is_absolute() && path.exists()
+    }
+
+    // Builds the section of a uprobe attached to a user binary, in the form
+    // of "rex/uprobe/<binary>:<target>[@<pid>]", e.g.
+    // "rex/uprobe//usr/bin/memcached:0x4a2b0@1234"
+    //
+    // If the binary is given by an absolute path and is present at compile
+    // time, `symbol` is resolved into a file offset here and any error in
+    // doing so aborts the compilation. Otherwise (e.g. when cross-building) the
+    // target is kept symbolic as "<symbol>[+<offset>]" and resolved by the
+    // loader when the program is attached.
+    fn uprobe_section(&self, flavor: &KprobeFlavor, binary: &str) -> String {
+        let target = match (&self.symbol, self.offset) {
+            (Some(symbol), offset) if Self::is_local(binary) => {
+                let sym_off = Elf::open(binary)
+                    .and_then(|elf| elf.symbol_offset(symbol))
+                    .unwrap_or_else(|err| {
+                        abort_call_site!("{}: {}", binary, err)
+                    });
+                let off =
+                    sym_off.checked_add(offset.unwrap_or(0)).unwrap_or_else(
+                        || abort_call_site!("`offset` overflows `{}`", symbol),
+                    );
+                format!("{off:#x}")
+            }
+            (Some(symbol), Some(offset)) => format!("{symbol}+{offset:#x}"),
+            (Some(symbol), None) => symbol.clone(),
+            (None, Some(offset)) => format!("{offset:#x}"),
+            (None, None) => {
+                abort_call_site!("`binary` needs a `symbol` or an `offset`")
+            }
+        };
+
+        match self.pid {
+            Some(pid) => format!("rex/{flavor}/{binary}:{target}@{pid}"),
+            None => format!("rex/{flavor}/{binary}:{target}"),
+        }
     }
 
     pub(crate) fn expand(&self, flavor: KprobeFlavor) -> Result<TokenStream> {
@@ -51,21 +166,59 @@ impl KProbe {
         let prog_ident =
             format_ident!("PROG_{}", fn_name.to_string().to_uppercase());
 
-        let attached_function = if self.function.is_some() {
-            format!("rex/{}/{}", flavor, self.function.as_ref().unwrap())
-        } else {
-            format!("rex/{flavor}")
-        };
+        if self.binary.is_some() &&
+            !matches!(flavor, KprobeFlavor::Uprobe | KprobeFlavor::Uretprobe)
+        {
+            abort_call_site!("`binary` is only supported by uprobes");
+        }
+
+        let (attached_function, constructor) =
+            match (&self.binary, &self.function, &self.functions) {
+                (Some(binary), _, _) => {
+                    (self.uprobe_section(&flavor, binary), quote!(new))
+                }
+                (None, Some(function), _) => {
+                    (format!("rex/{flavor}/{function}"), quote!(new))
+                }
+                // Multi-attach: all patterns are encoded in the section name,
+                // e.g. "rex/kprobe.multi/tcp_*,udp_sendmsg"
+                (None, None, Some(_))
+                    if !matches!(
+                        flavor,
+                        KprobeFlavor::Kprobe | KprobeFlavor::Kretprobe
+                    ) =>
+                {
+                    abort_call_site!("`functions` is only supported by kprobes")
+                }
+                (None, None, Some(functions)) => (
+                    format!("rex/{}.multi/{}", flavor, functions.join(",")),
+                    quote!(new_multi),
+                ),
+                (None, None, None) => (format!("rex/{flavor}"), quote!(new)),
+            };
 
         let entry_name = format_ident!("__rex_entry_{}", fn_name);
 
+        // Makes the compiler re-expand the macro when a binary resolved at
+        // compile time changes, the bytes are never emitted
+        let track_binary = match (&self.binary, &self.symbol) {
+            (Some(binary), Some(_)) if Self::is_local(binary) => {
+                quote!(
+                    const _: &[u8] = include_bytes!(#binary);
+                )
+            }
+            _ => quote!(),
+        };
+
         let function_body_tokens = quote! {
+            #track_binary
+
             #[inline(always)]
             #item
 
             #[used]
             static #prog_ident: kprobe =
-                unsafe { kprobe::new(#fn_name) };
+                unsafe { kprobe::#constructor(#fn_name) };
 
             #[unsafe(export_name = #function_name)]
             #[unsafe(link_section = #attached_function)]
@@ -1,21 +1,50 @@
 #[macro_use]
 pub(crate) mod args;
+mod cgroup_skb;
+mod cgroup_sysctl;
+mod elf;
+mod flow_dissector;
+mod iter;
 mod kprobe;
+mod lsm;
+mod netfilter;
 mod perf_event;
+mod sk_lookup;
+mod sk_msg;
+mod sk_reuseport;
+mod sk_skb;
+mod sock_ops;
+mod socket_filter;
+mod struct_ops;
 mod tc;
 mod tracepoint;
+mod usdt;
 mod xdp;
 
 use std::borrow::Cow;
 
+use cgroup_skb::CgroupSkb;
+use cgroup_sysctl::CgroupSysctl;
+use flow_dissector::FlowDissector;
+use iter::Iter;
 use kprobe::{KProbe, KprobeFlavor};
+use lsm::Lsm;
+use netfilter::Netfilter;
 use perf_event::PerfEvent;
 use proc_macro::TokenStream;
 use proc_macro_error::{abort, proc_macro_error};
 use quote::quote;
+use sk_lookup::SkLookup;
+use sk_msg::SkMsg;
+use sk_reuseport::SkReuseport;
+use sk_skb::SkSkb;
+use sock_ops::SockOps;
+use socket_filter::SocketFilter;
+use struct_ops::TcpCongestionOps;
 use syn::ItemStatic;
 use tc::SchedCls;
 use tracepoint::TracePoint;
+use usdt::Usdt;
 use xdp::Xdp;
 
 #[proc_macro_error]
@@ -42,6 +71,129 @@ pub fn rex_tc(attrs: TokenStream, item: TokenStream) -> TokenStream {
     }
 }
 
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn rex_cgroup_skb(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match CgroupSkb::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog
+            .expand()
+            .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+            .into(),
+        Err(err) => abort!(err.span(), "{}", err),
+    }
+}
+
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn rex_cgroup_sysctl(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match CgroupSysctl::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog
+            .expand()
+            .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+            .into(),
+        Err(err) => abort!(err.span(), "{}", err),
+    }
+}
+
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn rex_flow_dissector(
+    attrs: TokenStream,
+    item: TokenStream,
+) -> TokenStream {
+    match FlowDissector::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog
+            .expand()
+            .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+            .into(),
+        Err(err) => abort!(err.span(), "{}", err),
+    }
+}
+
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn rex_sock_ops(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match SockOps::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog
+            .expand()
+            .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+            .into(),
+        Err(err) => abort!(err.span(), "{}", err),
+    }
+}
+
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn rex_socket_filter(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match SocketFilter::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog
+            .expand()
+            .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+            .into(),
+        Err(err) => abort!(err.span(), "{}", err),
+    }
+}
+
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn rex_sk_lookup(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match SkLookup::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog
+            .expand()
+            .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+            .into(),
+        Err(err) => abort!(err.span(), "{}", err),
+    }
+}
+
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn rex_sk_reuseport(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match SkReuseport::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog
+            .expand()
+            .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+            .into(),
+        Err(err) => abort!(err.span(), "{}", err),
+    }
+}
+
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn rex_sk_msg(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match SkMsg::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog
+            .expand()
+            .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+            .into(),
+        Err(err) => abort!(err.span(), "{}", err),
+    }
+}
+
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn rex_sk_skb(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match SkSkb::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog
+            .expand()
+            .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+            .into(),
+        Err(err) => abort!(err.span(), "{}", err),
+    }
+}
+
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn rex_netfilter(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match Netfilter::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog
+            .expand()
+            .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+            .into(),
+        Err(err) => abort!(err.span(), "{}", err),
+    }
+}
+
 #[proc_macro_error]
 #[proc_macro_attribute]
 pub fn rex_kprobe(attrs: TokenStream, item: TokenStream) -> TokenStream {
@@ -66,6 +218,18 @@ pub fn rex_uprobe(attrs: TokenStream, item: TokenStream) -> TokenStream {
     }
 }
 
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn rex_usdt(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match Usdt::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog
+            .expand()
+            .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+            .into(),
+        Err(err) => abort!(err.span(), "{}", err),
+    }
+}
+
 #[proc_macro_error]
 #[proc_macro_attribute]
 pub fn rex_tracepoint(attrs: TokenStream, item: TokenStream) -> TokenStream {
@@ -78,6 +242,30 @@ pub fn rex_tracepoint(attrs: TokenStream, item: TokenStream) -> TokenStream {
     }
 }
 
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn rex_lsm(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match Lsm::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog
+            .expand()
+            .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+            .into(),
+        Err(err) => abort!(err.span(), "{}", err),
+    }
+}
+
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn rex_iter(attrs: TokenStream, item: TokenStream) -> TokenStream {
+    match Iter::parse(attrs.into(), item.into()) {
+        Ok(prog) => prog
+            .expand()
+            .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+            .into(),
+        Err(err) => abort!(err.span(), "{}", err),
+    }
+}
+
 #[proc_macro_error]
 #[proc_macro_attribute]
 pub fn rex_perf_event(attrs: TokenStream, item: TokenStream) -> TokenStream {
@@ -90,6 +278,21 @@ pub fn rex_perf_event(attrs: TokenStream, item: TokenStream) -> TokenStream {
     }
 }
 
+#[proc_macro_error]
+#[proc_macro_attribute]
+pub fn rex_tcp_congestion_ops(
+    attrs: TokenStream,
+    item: TokenStream,
+) -> TokenStream {
+    match TcpCongestionOps::parse(attrs.into(), item.into()) {
+        Ok(ops) => ops
+            .expand()
+            .unwrap_or_else(|err| abort!(err.span(), "{}", err))
+            .into(),
+        Err(err) => abort!(err.span(), "{}", err),
+    }
+}
+
 /// Ref: <https://github.com/aya-rs/aya/blob/1cf3d3c222bda0351ee6a2bacf9cee5349556764/aya-ebpf-macros/src/lib.rs#L53>
 #[proc_macro_attribute]
 pub fn rex_map(_: TokenStream, item: TokenStream) -> TokenStream {
@@ -0,0 +1,102 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort_call_site;
+use quote::{format_ident, quote};
+use syn::{parse2, ItemFn, Result};
+
+use crate::args::parse_args;
+
+/// `enum nf_inet_hooks`, the names are resolved by the loader
+const NF_INET_HOOKS: &[&str] =
+    &["prerouting", "input", "forward", "output", "postrouting"];
+
+/// `NFPROTO_IPV4` and `NFPROTO_IPV6`
+const NF_PROTOS: &[&str] = &["ipv4", "ipv6"];
+
+pub(crate) struct Netfilter {
+    family: String,
+    hook: String,
+    priority: i32,
+    item: ItemFn,
+}
+
+impl Netfilter {
+    // parse the argument of function
+    pub(crate) fn parse(
+        attrs: TokenStream,
+        item: TokenStream,
+    ) -> Result<Netfilter> {
+        let item: ItemFn = parse2(item)?;
+        let args = parse_args(attrs)?;
+
+        let Some(hook) = pop_string_args!(args, "hook") else {
+            abort_call_site!("`hook` is required");
+        };
+        if !NF_INET_HOOKS.contains(&hook.as_str()) {
+            abort_call_site!(
+                "Invalid hook `{}`, expected one of `prerouting`, `input`, \
+                 `forward`, `output` or `postrouting`",
+                hook
+            );
+        }
+
+        let family =
+            pop_string_args!(args, "family").unwrap_or("ipv4".to_string());
+        if !NF_PROTOS.contains(&family.as_str()) {
+            abort_call_site!(
+                "Invalid family `{}`, expected `ipv4` or `ipv6`",
+                family
+            );
+        }
+
+        // The kernel reserves the lowest and the highest priorities
+        let Some(priority) = pop_int_args!(args, "priority", i32) else {
+            abort_call_site!("`priority` is required");
+        };
+        if priority == i32::MIN || priority == i32::MAX {
+            abort_call_site!(
+                "`priority` must be in ({}, {})",
+                i32::MIN,
+                i32::MAX
+            );
+        }
+
+        Ok(Netfilter {
+            family,
+            hook,
+            priority,
+            item,
+        })
+    }
+
+    pub(crate) fn expand(&self) -> Result<TokenStream> {
+        let fn_name = self.item.sig.ident.clone();
+        let item = &self.item;
+        let function_name = format!("{fn_name}");
+        let prog_ident =
+            format_ident!("PROG_{}", fn_name.to_string().to_uppercase());
+        // The loader parses the attach options from the section name
+        let section_name = format!(
+            "rex/netfilter/{}/{}/{}",
+            self.family, self.hook, self.priority
+        );
+
+        let entry_name = format_ident!("__rex_entry_{}", fn_name);
+
+        let function_body_tokens = quote! {
+            #[inline(always)]
+            #item
+
+            #[used]
+            static #prog_ident: netfilter =
+                unsafe { netfilter::new(#fn_name) };
+
+            #[unsafe(export_name = #function_name)]
+            #[unsafe(link_section = #section_name)]
+            extern "C" fn #entry_name(ctx: *mut ()) -> u32 {
+                use rex::prog_type::rex_prog;
+                #prog_ident.prog_run(ctx)
+            }
+        };
+        Ok(function_body_tokens)
+    }
+}
@@ -0,0 +1,63 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort_call_site;
+use quote::{format_ident, quote};
+use syn::{parse2, ItemFn, Result};
+
+use crate::args::parse_args;
+
+pub(crate) struct SkSkb {
+    kind: String,
+    item: ItemFn,
+}
+
+impl SkSkb {
+    // parse the argument of function
+    pub(crate) fn parse(
+        attrs: TokenStream,
+        item: TokenStream,
+    ) -> Result<SkSkb> {
+        let item: ItemFn = parse2(item)?;
+        let args = parse_args(attrs)?;
+
+        let kind = match pop_string_args!(args, "kind") {
+            Some(kind) if kind == "stream_parser" || kind == "stream_verdict" => {
+                kind
+            }
+            Some(kind) => abort_call_site!(
+                "Invalid kind `{}`, expected `stream_parser` or `stream_verdict`",
+                kind
+            ),
+            None => abort_call_site!("`kind` is required"),
+        };
+
+        Ok(SkSkb { kind, item })
+    }
+
+    pub(crate) fn expand(&self) -> Result<TokenStream> {
+        let fn_name = self.item.sig.ident.clone();
+        let item = &self.item;
+        let function_name = format!("{fn_name}");
+        let prog_ident =
+            format_ident!("PROG_{}", fn_name.to_string().to_uppercase());
+        let attached_name = format!("rex/sk_skb/{}", self.kind);
+
+        let entry_name = format_ident!("__rex_entry_{}", fn_name);
+
+        let function_body_tokens = quote! {
+            #[inline(always)]
+            #item
+
+            #[used]
+            static #prog_ident: sk_skb =
+                unsafe { sk_skb::new(#fn_name) };
+
+            #[unsafe(export_name = #function_name)]
+            #[unsafe(link_section = #attached_name)]
+            extern "C" fn #entry_name(ctx: *mut ()) -> u32 {
+                use rex::prog_type::rex_prog;
+                #prog_ident.prog_run(ctx)
+            }
+        };
+        Ok(function_body_tokens)
+    }
+}
@@ -0,0 +1,156 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort_call_site;
+use quote::{format_ident, quote};
+use syn::{parse2, ImplItem, ItemImpl, Result, Type};
+
+use crate::args::parse_args;
+
+/// `TCP_CA_NAME_MAX`, including the NUL terminator
+const TCP_CA_NAME_MAX: usize = 16;
+
+/// Callbacks of `struct tcp_congestion_ops` supported by Rex, paired with
+/// their variant of `TcpCongestionOpsCallback`
+const TCP_CA_CALLBACKS: &[(&str, &str)] = &[
+    ("ssthresh", "Ssthresh"),
+    ("cong_avoid", "CongAvoid"),
+    ("set_state", "SetState"),
+    ("cwnd_event", "CwndEvent"),
+    ("in_ack_event", "InAckEvent"),
+    ("pkts_acked", "PktsAcked"),
+    ("min_tso_segs", "MinTsoSegs"),
+    ("undo_cwnd", "UndoCwnd"),
+    ("sndbuf_expand", "SndbufExpand"),
+    ("init", "Init"),
+    ("release", "Release"),
+];
+
+pub(crate) struct TcpCongestionOps {
+    name: String,
+    item: ItemImpl,
+}
+
+impl TcpCongestionOps {
+    // parse the argument of the impl block
+    pub(crate) fn parse(
+        attrs: TokenStream,
+        item: TokenStream,
+    ) -> Result<TcpCongestionOps> {
+        let item: ItemImpl = parse2(item)?;
+        let args = parse_args(attrs)?;
+
+        let Some(name) = pop_string_args!(args, "name") else {
+            abort_call_site!("`name` is required");
+        };
+        if name.is_empty() ||
+            name.len() >= TCP_CA_NAME_MAX ||
+            !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            abort_call_site!(
+                "`name` must be 1 to {} alphanumeric or `_` characters",
+                TCP_CA_NAME_MAX - 1
+            );
+        }
+
+        if item.trait_.is_none() {
+            abort_call_site!(
+                "Expected an implementation of `TcpCongestionOps`"
+            );
+        }
+
+        Ok(TcpCongestionOps { name, item })
+    }
+
+    // Each implemented callback becomes a standalone program in
+    // "rex/struct_ops/<callback>", e.g. for `ssthresh`:
+    //
+    // static PROG_RENO_SSTHRESH: tcp_congestion_ops<Reno> =
+    //     tcp_congestion_ops::new(TcpCongestionOpsCallback::Ssthresh);
+    //
+    // The programs are then tied together by a `RexTcpCongestionOps` static
+    // in the ".struct_ops" section, which the loader registers as the
+    // struct_ops map
+    pub(crate) fn expand(&self) -> Result<TokenStream> {
+        let item = &self.item;
+        let self_ty = &self.item.self_ty;
+        if !matches!(**self_ty, Type::Path(_)) {
+            abort_call_site!("Congestion control type needs to be a path");
+        }
+
+        let implemented: Vec<String> = self
+            .item
+            .items
+            .iter()
+            .filter_map(|i| match i {
+                ImplItem::Fn(f) => Some(f.sig.ident.to_string()),
+                _ => None,
+            })
+            .collect();
+
+        let mut progs = Vec::new();
+        let mut fields = Vec::new();
+
+        for (cb, variant) in TCP_CA_CALLBACKS {
+            let field = format_ident!("{}", cb);
+
+            if !implemented.iter().any(|f| f == cb) {
+                fields.push(quote!(#field: None));
+                continue;
+            }
+
+            let function_name = format!("{}_{}", self.name, cb);
+            let prog_ident =
+                format_ident!("PROG_{}", function_name.to_uppercase());
+            let entry_name = format_ident!("__rex_entry_{}", function_name);
+            let attached_name = format!("rex/struct_ops/{cb}");
+            let variant = format_ident!("{}", variant);
+
+            progs.push(quote! {
+                #[used]
+                static #prog_ident:
+                    rex::struct_ops::tcp_congestion_ops<#self_ty> = unsafe {
+                        rex::struct_ops::tcp_congestion_ops::new(
+                            rex::struct_ops::TcpCongestionOpsCallback::#variant
+                        )
+                    };
+
+                #[unsafe(export_name = #function_name)]
+                #[unsafe(link_section = #attached_name)]
+                extern "C" fn #entry_name(ctx: *mut ()) -> u32 {
+                    use rex::prog_type::rex_prog;
+                    #prog_ident.prog_run(ctx)
+                }
+            });
+            fields.push(quote!(#field: Some(#entry_name)));
+        }
+
+        let mut name_bytes = [0u8; TCP_CA_NAME_MAX];
+        name_bytes[..self.name.len()].copy_from_slice(self.name.as_bytes());
+        // The loader looks up the kernel struct from the symbol name and
+        // registers the object as a struct_ops map named after the algorithm
+        let ops_name = format!("tcp_congestion_ops.{}", self.name);
+        let ops_ident = format_ident!("OPS_{}", self.name.to_uppercase());
+
+        let function_body_tokens = quote! {
+            #item
+
+            #(#progs)*
+
+            #[used]
+            #[unsafe(export_name = #ops_name)]
+            #[unsafe(link_section = ".struct_ops")]
+            static #ops_ident: rex::struct_ops::RexTcpCongestionOps =
+                rex::struct_ops::RexTcpCongestionOps {
+                    #(#fields,)*
+                    cong_control: None,
+                    get_info: None,
+                    name: [#(#name_bytes),*],
+                    owner: 0,
+                    list: [0; 2],
+                    key: 0,
+                    flags: 0,
+                };
+        };
+
+        Ok(function_body_tokens)
+    }
+}
@@ -0,0 +1,93 @@
+use proc_macro2::TokenStream;
+use proc_macro_error::abort_call_site;
+use quote::{format_ident, quote};
+use syn::{parse2, ItemFn, Result};
+
+use crate::args::parse_args;
+
+pub(crate) struct Usdt {
+    binary: Option<String>,
+    provider: String,
+    name: String,
+    item: ItemFn,
+}
+
+impl Usdt {
+    // parse the argument of function
+    pub(crate) fn parse(attrs: TokenStream, item: TokenStream) -> Result<Usdt> {
+        let item: ItemFn = parse2(item)?;
+        let args = parse_args(attrs)?;
+
+        let binary = pop_string_args!(args, "binary");
+        let (Some(provider), Some(name)) = (
+            pop_string_args!(args, "provider"),
+            pop_string_args!(args, "name"),
+        ) else {
+            abort_call_site!("`provider` and `name` are required");
+        };
+
+        // The loader splits the section name at ':'
+        if provider.contains(':') || name.contains(':') {
+            abort_call_site!("`provider` and `name` cannot contain ':'");
+        }
+
+        Ok(Usdt {
+            binary,
+            provider,
+            name,
+            item,
+        })
+    }
+
+    // The probe is named in the section as "rex/usdt/<provider>:<name>",
+    // prefixed by "<binary>:" if the binary is known in advance, e.g.
+    // "rex/usdt//usr/bin/memcached:memcached:process__command". Programs
+    // without a binary are attached with `rex_prog_attach_usdt`.
+    //
+    // The probe sites and their argument specs are read from the
+    // `.note.stapsdt` section of the binary by the loader when the program is
+    // attached. The specs are stored in a per-program map, indexed by the BPF
+    // cookie of each site.
+    pub(crate) fn expand(&self) -> Result<TokenStream> {
+        let fn_name = self.item.sig.ident.clone();
+        let item = &self.item;
+        let function_name = format!("{fn_name}");
+        let prog_ident =
+            format_ident!("PROG_{}", fn_name.to_string().to_uppercase());
+        let specs_ident =
+            format_ident!("SPECS_{}", fn_name.to_string().to_uppercase());
+        let specs_name = format!("__rex_usdt_specs_{fn_name}");
+
+        let attached_name = match &self.binary {
+            Some(binary) => {
+                format!("rex/usdt/{}:{}:{}", binary, self.provider, self.name)
+            }
+            None => format!("rex/usdt/{}:{}", self.provider, self.name),
+        };
+
+        let entry_name = format_ident!("__rex_entry_{}", fn_name);
+
+        let function_body_tokens = quote! {
+            #[inline(always)]
+            #item
+
+            #[unsafe(link_section = ".maps")]
+            #[unsafe(export_name = #specs_name)]
+            static #specs_ident: rex::map::RexArrayMap<rex::usdt::UsdtSpec> =
+                rex::map::RexArrayMap::new(rex::usdt::USDT_MAX_SPECS, 0);
+
+            #[used]
+            static #prog_ident: usdt =
+                unsafe { usdt::new(#fn_name, &#specs_ident) };
+
+            #[unsafe(export_name = #function_name)]
+            #[unsafe(link_section = #attached_name)]
+            extern "C" fn #entry_name(ctx: *mut ()) -> u32 {
+                use rex::prog_type::rex_prog;
+                #prog_ident.prog_run(ctx)
+            }
+        };
+
+        Ok(function_body_tokens)
+    }
+}
@@ -2,15 +2,24 @@ use proc_macro2::TokenStream;
 use quote::{format_ident, quote};
 use syn::{ItemFn, Result};
 
+use crate::args::parse_args;
+
 pub(crate) struct Xdp {
+    frags: bool,
     item: ItemFn,
 }
 
 impl Xdp {
     // parse the argument of function
-    pub(crate) fn parse(_: TokenStream, item: TokenStream) -> Result<Xdp> {
+    pub(crate) fn parse(attrs: TokenStream, item: TokenStream) -> Result<Xdp> {
         let item = syn::parse2(item)?;
-        Ok(Xdp { item })
+        let args = parse_args(attrs)?;
+
+        // Programs supporting multi-buffer packets (e.g. jumbo frames) are
+        // loaded with BPF_F_XDP_HAS_FRAGS
+        let frags = pop_flag_args!(args, "frags");
+
+        Ok(Xdp { frags, item })
     }
 
     // expand the function into two function with original function
@@ -26,6 +35,11 @@ impl Xdp {
             format_ident!("PROG_{}", fn_name.to_string().to_uppercase());
 
         let entry_name = format_ident!("__rex_entry_{}", fn_name);
+        let attached_name = if self.frags {
+            "rex/xdp.frags"
+        } else {
+            "rex/xdp"
+        };
 
         let function_body_tokens = quote! {
             #[inline(always)]
@@ -36,7 +50,7 @@ impl Xdp {
                 unsafe { xdp::new(#fn_name) };
 
             #[unsafe(export_name = #function_name)]
-            #[unsafe(link_section = "rex/xdp")]
+            #[unsafe(link_section = #attached_name)]
             extern "C" fn #entry_name(ctx: *mut ()) -> u32 {
                 use rex::prog_type::rex_prog;
                 #prog_ident.prog_run(ctx)
@@ -15,8 +15,13 @@ k_structs = ['task_struct', 'tk_read_base', 'seqcount_raw_spinlock_t',
              'clocksource', 'seqcount_t', 'seqcount_latch_t', 'timekeeper',
              'kcsan_ctx', 'rnd_state', 'timespec64', 'bpf_spin_lock',
              'bpf_sysctl_kern', 'xdp_buff', 'ethhdr', 'iphdr', 'tcphdr',
-             'udphdr', 'sk_buff', 'sock', 'pcpu_hot',
-             'bpf_perf_event_data_kern']
+             'udphdr', 'sk_buff', 'sk_msg', 'sock', 'pcpu_hot',
+             'bpf_perf_event_data_kern', 'file', 'tcp_sock',
+             'bpf_sock_ops_kern', 'bpf_sk_lookup_kern', 'sk_reuseport_kern',
+             'bpf_flow_dissector', 'bpf_iter_meta', 'bpf_nf_ctx',
+             'skb_shared_info', 'vlan_hdr', 'ipv6hdr', 'icmphdr',
+             'icmp6hdr', 'arphdr', 'ipv6_opt_hdr', 'ipv6_rt_hdr', 'frag_hdr',
+             'nf_conn', 'tcp_congestion_ops', 'bpf_map']
 
 bindgen_kernel_cmd = '''bindgen %s --allowlist-type="%s"
 --allowlist-var="(___GFP.*|CONFIG_.*|MAX_BPRINTF_BUF)"
@@ -11,6 +11,7 @@ KSYM_FUNC(bpf_map_push_elem)
 KSYM_FUNC(bpf_map_pop_elem)
 KSYM_FUNC(bpf_map_peek_elem)
 KSYM_FUNC(bpf_probe_read_kernel)
+KSYM_FUNC(bpf_probe_read_user)
 KSYM_FUNC(ktime_get_mono_fast_ns)
 KSYM_FUNC(ktime_get_boot_fast_ns)
 KSYM_FUNC(get_random_u32)
@@ -20,6 +21,9 @@ KSYM_FUNC(rex_landingpad)
 KSYM_FUNC(bpf_spin_lock)
 KSYM_FUNC(bpf_spin_unlock)
 KSYM_FUNC(just_return_func)
+KSYM_FUNC(bpf_get_func_ip_kprobe)
+KSYM_FUNC(bpf_get_func_ip_kprobe_multi)
+KSYM_FUNC(bpf_get_attach_cookie_trace)
 KSYM_FUNC(bpf_get_stackid_pe)
 KSYM_FUNC(bpf_perf_prog_read_value)
 KSYM_FUNC(bpf_perf_event_output_tp)
@@ -28,6 +32,10 @@ KSYM_FUNC(bpf_skb_event_output)
 KSYM_FUNC(bpf_xdp_event_output)
 KSYM_FUNC(bpf_xdp_adjust_head)
 KSYM_FUNC(bpf_xdp_adjust_tail)
+KSYM_FUNC(bpf_xdp_adjust_meta)
+KSYM_FUNC(bpf_xdp_load_bytes)
+KSYM_FUNC(bpf_xdp_store_bytes)
+KSYM_FUNC(bpf_xdp_get_buff_len)
 KSYM_FUNC(bpf_clone_redirect)
 KSYM_FUNC(bpf_ringbuf_output)
 KSYM_FUNC(bpf_ringbuf_reserve)
@@ -37,6 +45,49 @@ KSYM_FUNC(bpf_ringbuf_query)
 KSYM_FUNC(bpf_ktime_get_ns)
 KSYM_FUNC(bpf_ktime_get_boot_ns)
 KSYM_FUNC(bpf_ktime_get_coarse_ns)
+KSYM_FUNC(bpf_msg_redirect_map)
+KSYM_FUNC(bpf_msg_redirect_hash)
+KSYM_FUNC(bpf_msg_apply_bytes)
+KSYM_FUNC(bpf_msg_cork_bytes)
+KSYM_FUNC(bpf_sk_redirect_map)
+KSYM_FUNC(bpf_sk_redirect_hash)
+KSYM_FUNC(tcp_slow_start)
+KSYM_FUNC(tcp_cong_avoid_ai)
+KSYM_FUNC(tcp_reno_ssthresh)
+KSYM_FUNC(tcp_reno_cong_avoid)
+KSYM_FUNC(tcp_reno_undo_cwnd)
+KSYM_FUNC(bpf_sysctl_get_name)
+KSYM_FUNC(bpf_sysctl_get_current_value)
+KSYM_FUNC(bpf_sysctl_get_new_value)
+KSYM_FUNC(bpf_sysctl_set_new_value)
+KSYM_FUNC(bpf_sock_ops_setsockopt)
+KSYM_FUNC(bpf_sock_ops_getsockopt)
+KSYM_FUNC(bpf_sock_ops_cb_flags_set)
+KSYM_FUNC(bpf_sk_lookup_assign)
+KSYM_FUNC(bpf_sk_release)
+KSYM_FUNC(sk_select_reuseport)
+KSYM_FUNC(bpf_seq_write)
+KSYM_FUNC(bpf_csum_diff)
+KSYM_FUNC(bpf_l3_csum_replace)
+KSYM_FUNC(bpf_l4_csum_replace)
+KSYM_FUNC(bpf_xdp_fib_lookup)
+KSYM_FUNC(bpf_skb_fib_lookup)
+KSYM_FUNC(bpf_sk_lookup_tcp)
+KSYM_FUNC(bpf_sk_lookup_udp)
+KSYM_FUNC(bpf_skc_lookup_tcp)
+KSYM_FUNC(bpf_xdp_sk_lookup_tcp)
+KSYM_FUNC(bpf_xdp_sk_lookup_udp)
+KSYM_FUNC(bpf_xdp_skc_lookup_tcp)
+KSYM_FUNC(bpf_xdp_ct_lookup)
+KSYM_FUNC(bpf_xdp_ct_alloc)
+KSYM_FUNC(bpf_skb_ct_lookup)
+KSYM_FUNC(bpf_skb_ct_alloc)
+KSYM_FUNC(bpf_ct_insert_entry)
+KSYM_FUNC(bpf_ct_release)
+KSYM_FUNC(bpf_ct_set_timeout)
+KSYM_FUNC(bpf_ct_change_timeout)
+KSYM_FUNC(bpf_ct_set_status)
+KSYM_FUNC(bpf_ct_change_status)
 KSYM_FUNC(rex_trace_printk)
 
 // Global variables
@@ -51,6 +51,7 @@ pub(crate) fn bpf_map_lookup_elem<'a, const MT: bpf_map_type, K, V>(
 ) -> Option<&'a mut V>
 where
     V: Copy + NoRef,
+    RexMapHandle<MT, K, V>: RexLookupMap,
 {
     let map_kptr = unsafe { core::ptr::read_volatile(&map.kptr) };
     if unlikely(map_kptr.is_null()) {
@@ -215,6 +216,22 @@ where
     })
 }
 
+pub(crate) fn bpf_probe_read_user<T>(
+    dst: &mut T,
+    unsafe_ptr: *const (),
+) -> Result
+where
+    T: Copy + NoRef,
+{
+    termination_check!(unsafe {
+        to_result!(ffi::bpf_probe_read_user(
+            dst as *mut T as *mut (),
+            core::mem::size_of::<T>() as u32,
+            unsafe_ptr,
+        ))
+    })
+}
+
 pub(crate) fn bpf_jiffies64() -> u64 {
     unsafe { core::ptr::read_volatile(&ffi::jiffies) }
 }
@@ -299,6 +316,7 @@ macro_rules! base_helper_defs {
         ) -> Option<&'b mut V>
         where
             V: Copy + crate::utils::NoRef,
+            crate::map::RexMapHandle<MT, K, V>: crate::map::RexLookupMap,
         {
             crate::base_helper::bpf_map_lookup_elem(map, key)
         }
@@ -0,0 +1 @@
+include!(concat!(env!("OUT_DIR"), "/uapi/linux/icmpv6.rs"));
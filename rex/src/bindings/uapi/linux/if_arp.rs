@@ -0,0 +1 @@
+include!(concat!(env!("OUT_DIR"), "/uapi/linux/if_arp.rs"));
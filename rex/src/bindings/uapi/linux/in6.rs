@@ -0,0 +1 @@
+include!(concat!(env!("OUT_DIR"), "/uapi/linux/in6.rs"));
@@ -1,6 +1,12 @@
 pub mod bpf;
 pub mod errno;
+pub mod icmp;
+pub mod icmpv6;
+pub mod if_arp;
+pub mod if_ether;
 pub mod r#in;
+pub mod in6;
+pub mod netfilter;
 pub mod perf_event;
 pub mod pkt_cls;
 pub mod ptrace;
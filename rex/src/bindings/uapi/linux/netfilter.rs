@@ -0,0 +1,3 @@
+include!(concat!(env!("OUT_DIR"), "/uapi/linux/netfilter.rs"));
+
+pub mod nf_conntrack_common;
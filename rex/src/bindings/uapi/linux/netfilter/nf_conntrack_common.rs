@@ -0,0 +1 @@
+include!(concat!(env!("OUT_DIR"), "/uapi/linux/netfilter/nf_conntrack_common.rs"));
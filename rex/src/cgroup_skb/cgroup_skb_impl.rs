@@ -0,0 +1,87 @@
+use core::ffi::c_int;
+use core::mem;
+
+pub use crate::bindings::linux::kernel::{iphdr, ipv6hdr};
+use crate::bindings::uapi::linux::bpf::{bpf_map_type, SK_DROP, SK_PASS};
+use crate::prog_type::rex_prog;
+pub use crate::sched_cls::__sk_buff;
+use crate::utils::*;
+
+/// Verdict of a cgroup_skb program on the packet
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CgroupSkbVerdict {
+    Deny = SK_DROP,
+    Allow = SK_PASS,
+}
+
+/// Return type of a cgroup_skb program, an `Err` denies the packet
+pub type CgroupSkbResult = core::result::Result<CgroupSkbVerdict, c_int>;
+
+/// prog_fn should have &Self as its first argument
+#[repr(C)]
+pub struct cgroup_skb {
+    prog: fn(&Self, &mut __sk_buff) -> CgroupSkbResult,
+}
+
+impl cgroup_skb {
+    crate::base_helper::base_helper_defs!();
+
+    pub const unsafe fn new(
+        f: fn(&cgroup_skb, &mut __sk_buff) -> CgroupSkbResult,
+    ) -> cgroup_skb {
+        Self { prog: f }
+    }
+
+    // The version of the IP header at the start of the packet
+    #[inline(always)]
+    fn ip_version(skb: &__sk_buff) -> Option<u8> {
+        skb.data_slice.first().map(|b| b >> 4)
+    }
+
+    /// The IPv4 header of the packet, `None` if the packet is not IPv4 or
+    /// is too short
+    // NOTE: unlike TC, the skb data of cgroup_skb starts at the network
+    // header on both ingress and egress, there is no ethhdr
+    #[inline(always)]
+    pub fn ip_header<'b>(
+        &self,
+        skb: &'b mut __sk_buff,
+    ) -> Option<AlignedMut<'b, iphdr>> {
+        if Self::ip_version(skb)? != 4 {
+            return None;
+        }
+
+        let hdr = skb.data_slice.get_mut(0..mem::size_of::<iphdr>())?;
+        Some(convert_slice_to_struct_mut::<iphdr>(hdr))
+    }
+
+    /// The IPv6 header of the packet, `None` if the packet is not IPv6 or
+    /// is too short
+    #[inline(always)]
+    pub fn ipv6_header<'b>(
+        &self,
+        skb: &'b mut __sk_buff,
+    ) -> Option<AlignedMut<'b, ipv6hdr>> {
+        if Self::ip_version(skb)? != 6 {
+            return None;
+        }
+
+        let hdr = skb.data_slice.get_mut(0..mem::size_of::<ipv6hdr>())?;
+        Some(convert_slice_to_struct_mut::<ipv6hdr>(hdr))
+    }
+
+    #[inline(always)]
+    fn convert_ctx(&self, ctx: *mut ()) -> __sk_buff {
+        __sk_buff::from_ctx(ctx)
+    }
+}
+
+impl rex_prog for cgroup_skb {
+    fn prog_run(&self, ctx: *mut ()) -> u32 {
+        let mut newctx = self.convert_ctx(ctx);
+        // deny the packet if error
+        ((self.prog)(self, &mut newctx)).unwrap_or(CgroupSkbVerdict::Deny)
+            as u32
+    }
+}
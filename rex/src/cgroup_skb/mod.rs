@@ -0,0 +1,3 @@
+mod cgroup_skb_impl;
+
+pub use cgroup_skb_impl::*;
@@ -0,0 +1,145 @@
+use crate::base_helper::termination_check;
+use crate::bindings::linux::kernel::bpf_sysctl_kern;
+use crate::bindings::uapi::linux::bpf::bpf_map_type;
+// expose the following constants to the user
+pub use crate::bindings::uapi::linux::bpf::BPF_F_SYSCTL_BASE_NAME;
+use crate::ffi;
+use crate::prog_type::rex_prog;
+use crate::utils::*;
+
+pub struct bpf_sysctl {
+    kptr: &'static mut bpf_sysctl_kern,
+}
+
+// Define accessors of program-accessible fields
+impl bpf_sysctl {
+    /// Whether the sysctl is being written (`true`) or read (`false`)
+    #[inline(always)]
+    pub fn write(&self) -> bool {
+        self.kptr.write != 0
+    }
+
+    /// Position in the sysctl file being accessed
+    #[inline(always)]
+    pub fn file_pos(&self) -> i64 {
+        unsafe { *self.kptr.ppos }
+    }
+}
+
+/// prog_fn should have &Self as its first argument
+///
+/// Returning `Ok` allows the access to the sysctl, returning `Err` rejects
+/// it and the syscall fails with `EPERM`.
+#[repr(C)]
+pub struct cgroup_sysctl {
+    prog: fn(&Self, &mut bpf_sysctl) -> Result,
+}
+
+impl cgroup_sysctl {
+    crate::base_helper::base_helper_defs!();
+
+    pub const unsafe fn new(
+        f: fn(&cgroup_sysctl, &mut bpf_sysctl) -> Result,
+    ) -> cgroup_sysctl {
+        Self { prog: f }
+    }
+
+    /// Copies the name of the sysctl into `buf` as a NUL-terminated string,
+    /// e.g. "net/ipv4/tcp_mem". Only the base name, e.g. "tcp_mem", is
+    /// copied if `BPF_F_SYSCTL_BASE_NAME` is set in `flags`.
+    ///
+    /// Returns the length of the name (excluding NUL), or `-E2BIG` if the
+    /// name was truncated.
+    #[inline(always)]
+    pub fn bpf_sysctl_get_name(
+        &self,
+        ctx: &mut bpf_sysctl,
+        buf: &mut [u8],
+        flags: u64,
+    ) -> Result {
+        termination_check!(unsafe {
+            to_result!(ffi::bpf_sysctl_get_name(
+                ctx.kptr,
+                buf.as_mut_ptr(),
+                buf.len(),
+                flags
+            ))
+        })
+    }
+
+    /// Copies the current value of the sysctl into `buf` as a NUL-terminated
+    /// string.
+    ///
+    /// Returns the length of the value (excluding NUL), or `-E2BIG` if the
+    /// value was truncated.
+    #[inline(always)]
+    pub fn bpf_sysctl_get_current_value(
+        &self,
+        ctx: &mut bpf_sysctl,
+        buf: &mut [u8],
+    ) -> Result {
+        termination_check!(unsafe {
+            to_result!(ffi::bpf_sysctl_get_current_value(
+                ctx.kptr,
+                buf.as_mut_ptr(),
+                buf.len()
+            ))
+        })
+    }
+
+    /// Copies the value being written to the sysctl into `buf` as a
+    /// NUL-terminated string.
+    ///
+    /// Returns the length of the value (excluding NUL), `-E2BIG` if the value
+    /// was truncated, or `-EINVAL` if the sysctl is being read.
+    #[inline(always)]
+    pub fn bpf_sysctl_get_new_value(
+        &self,
+        ctx: &mut bpf_sysctl,
+        buf: &mut [u8],
+    ) -> Result {
+        termination_check!(unsafe {
+            to_result!(ffi::bpf_sysctl_get_new_value(
+                ctx.kptr,
+                buf.as_mut_ptr(),
+                buf.len()
+            ))
+        })
+    }
+
+    /// Overrides the value being written to the sysctl with `val`.
+    ///
+    /// Returns `-EINVAL` if the sysctl is being read, or `-E2BIG` if `val` is
+    /// too long.
+    #[inline(always)]
+    pub fn bpf_sysctl_set_new_value(
+        &self,
+        ctx: &mut bpf_sysctl,
+        val: &[u8],
+    ) -> Result {
+        termination_check!(unsafe {
+            to_result!(ffi::bpf_sysctl_set_new_value(
+                ctx.kptr,
+                val.as_ptr(),
+                val.len()
+            ))
+        })
+    }
+
+    #[inline(always)]
+    fn convert_ctx(&self, ctx: *mut ()) -> bpf_sysctl {
+        let kptr = unsafe { &mut *(ctx as *mut bpf_sysctl_kern) };
+        bpf_sysctl { kptr }
+    }
+}
+
+impl rex_prog for cgroup_sysctl {
+    fn prog_run(&self, ctx: *mut ()) -> u32 {
+        let mut newctx = self.convert_ctx(ctx);
+        // 1 allows the access and 0 rejects it
+        match (self.prog)(self, &mut newctx) {
+            Ok(_) => 1,
+            Err(_) => 0,
+        }
+    }
+}
@@ -0,0 +1,3 @@
+mod cgroup_sysctl_impl;
+
+pub use cgroup_sysctl_impl::*;
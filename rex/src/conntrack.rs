@@ -0,0 +1,276 @@
+//! Access to the netfilter connection tracking table from packet programs
+//!
+//! The kfuncs live in the conntrack module, which must be loaded for programs
+//! using them to be loaded.
+
+use core::mem::{self, size_of};
+
+use crate::base_helper::termination_check;
+use crate::bindings::linux::kernel::nf_conn;
+use crate::bindings::uapi::linux::bpf::bpf_sock_tuple;
+// expose the following constants to the user
+pub use crate::bindings::uapi::linux::netfilter::nf_conntrack_common::{
+    IPS_ASSURED, IPS_CONFIRMED, IPS_DYING, IPS_EXPECTED, IPS_SEEN_REPLY,
+};
+use crate::ffi;
+use crate::panic::CleanupEntries;
+use crate::socket::SockTuple;
+use crate::utils::*;
+
+/// `struct bpf_ct_opts` of net/netfilter/nf_conntrack_bpf.c, which is not
+/// part of any header
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub(crate) struct bpf_ct_opts {
+    netns_id: i32,
+    error: i32,
+    l4proto: u8,
+    dir: u8,
+    ct_zone_id: u16,
+    ct_zone_dir: u8,
+    reserved: [u8; 3],
+}
+
+// BPF_F_CURRENT_NETNS, the conntrack kfuncs take an s32 netns_id
+const CURRENT_NETNS: i32 = -1;
+
+/// Options of a conntrack lookup or allocation, the lookup is always done in
+/// the network namespace of the packet
+#[derive(Debug, Copy, Clone)]
+pub struct CtOpts {
+    /// `IPPROTO_TCP` or `IPPROTO_UDP`
+    pub l4proto: u8,
+    /// Conntrack zone, 0 is the default zone
+    pub zone_id: u16,
+    /// Directions the zone applies to (`NF_CT_ZONE_DIR_*`), only used with
+    /// a non-zero `zone_id`
+    pub zone_dir: u8,
+}
+
+impl CtOpts {
+    /// Options for a lookup of `l4proto` in the default zone
+    pub fn new(l4proto: u8) -> Self {
+        Self {
+            l4proto,
+            zone_id: 0,
+            zone_dir: 0,
+        }
+    }
+
+    fn to_raw(self) -> bpf_ct_opts {
+        bpf_ct_opts {
+            netns_id: CURRENT_NETNS,
+            l4proto: self.l4proto,
+            ct_zone_id: self.zone_id,
+            ct_zone_dir: self.zone_dir,
+            ..Default::default()
+        }
+    }
+}
+
+/// Direction of the tuple a conntrack entry was found with
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CtDir {
+    /// The tuple is in the direction of the packet creating the connection
+    Original,
+    /// The tuple is in the direction of the replies
+    Reply,
+}
+
+/// Function that releases the entry, used by cleanup list
+unsafe fn ct_panic_cleanup(ct: *mut ()) {
+    if !ct.is_null() {
+        unsafe { ffi::bpf_ct_release(ct as *mut nf_conn) };
+    }
+}
+
+/// Runs a lookup or allocation kfunc and returns the acquired entry with its
+/// cleanup entry, or the error reported by the kfunc
+#[inline(always)]
+pub(crate) fn ct_acquire<F>(
+    tuple: &SockTuple,
+    opts: &CtOpts,
+    acquire_fn: F,
+) -> core::result::Result<(*mut nf_conn, usize, u8), i32>
+where
+    F: FnOnce(*mut bpf_sock_tuple, u32, *mut bpf_ct_opts, u32) -> *mut nf_conn,
+{
+    let (mut raw_tuple, tuple_size) = tuple.to_raw();
+    let mut raw_opts = opts.to_raw();
+
+    termination_check!({
+        // Put it before the kfunc so if it panics we will not be holding a
+        // reference without a valid cleanup entry for it, the entry is filled
+        // in within the same check
+        let cleanup_idx = CleanupEntries::register_cleanup(
+            ct_panic_cleanup,
+            core::ptr::null_mut(),
+        );
+
+        let ct = acquire_fn(
+            &mut raw_tuple,
+            tuple_size,
+            &mut raw_opts,
+            size_of::<bpf_ct_opts>() as u32,
+        );
+
+        if ct.is_null() {
+            CleanupEntries::deregister_cleanup(cleanup_idx);
+            Err(raw_opts.error)
+        } else {
+            CleanupEntries::set_cleanup_arg(cleanup_idx, ct as *mut ());
+            Ok((ct, cleanup_idx, raw_opts.dir))
+        }
+    })
+}
+
+/// An RAII implementation of a reference to an entry in the conntrack table,
+/// acquired by `bpf_ct_lookup` or [`ConntrackInit::insert`]. When this
+/// structure is dropped (falls out of scope), the reference is released with
+/// `bpf_ct_release`.
+#[must_use = "if unused the entry will immediately be released"]
+#[clippy::has_significant_drop]
+pub struct Conntrack {
+    ct: *mut nf_conn,
+    cleanup_idx: usize,
+    dir: CtDir,
+}
+
+impl Conntrack {
+    #[inline(always)]
+    pub(crate) fn new(ct: *mut nf_conn, cleanup_idx: usize, dir: u8) -> Self {
+        let dir = if dir == 0 {
+            CtDir::Original
+        } else {
+            CtDir::Reply
+        };
+
+        Self {
+            ct,
+            cleanup_idx,
+            dir,
+        }
+    }
+
+    /// Status bits of the entry (`IPS_*`), e.g. `IPS_SEEN_REPLY` once the
+    /// connection has seen traffic in both directions
+    #[inline(always)]
+    pub fn status(&self) -> u64 {
+        unsafe { (*self.ct).status }
+    }
+
+    /// The direction of the tuple the entry was looked up with, always
+    /// `CtDir::Original` for an inserted entry
+    #[inline(always)]
+    pub fn dir(&self) -> CtDir {
+        self.dir
+    }
+
+    /// Sets the timeout of the entry to `timeout` milliseconds from now
+    #[inline(always)]
+    pub fn change_timeout(&mut self, timeout: u32) -> Result {
+        termination_check!(unsafe {
+            to_result!(ffi::bpf_ct_change_timeout(self.ct, timeout))
+        })
+    }
+
+    /// Sets the status bits of the entry, bits that cannot be changed at
+    /// runtime (e.g. `IPS_CONFIRMED`) are ignored
+    #[inline(always)]
+    pub fn change_status(&mut self, status: u32) -> Result {
+        termination_check!(unsafe {
+            to_result!(ffi::bpf_ct_change_status(self.ct, status))
+        })
+    }
+}
+
+impl Drop for Conntrack {
+    /// Release the entry when the guard is out-of-scope
+    fn drop(&mut self) {
+        termination_check!({
+            // Put it before release so if it panics we will not release twice
+            CleanupEntries::deregister_cleanup(self.cleanup_idx);
+
+            unsafe { ffi::bpf_ct_release(self.ct) };
+        })
+    }
+}
+
+/// Unimplement Send and Sync
+impl !Send for Conntrack {}
+impl !Sync for Conntrack {}
+
+/// An RAII implementation of a conntrack entry allocated by `bpf_ct_alloc`
+/// that is not in the table yet. The entry is freed when this structure is
+/// dropped without being inserted.
+#[must_use = "if unused the entry will immediately be freed"]
+#[clippy::has_significant_drop]
+pub struct ConntrackInit {
+    ct: *mut nf_conn,
+    cleanup_idx: usize,
+}
+
+impl ConntrackInit {
+    #[inline(always)]
+    pub(crate) fn new(ct: *mut nf_conn, cleanup_idx: usize) -> Self {
+        Self { ct, cleanup_idx }
+    }
+
+    /// Sets the timeout of the entry to `timeout` milliseconds after its
+    /// insertion
+    #[inline(always)]
+    pub fn set_timeout(&mut self, timeout: u32) {
+        termination_check!(unsafe { ffi::bpf_ct_set_timeout(self.ct, timeout) })
+    }
+
+    /// Sets the status bits of the entry, e.g. `IPS_SEEN_REPLY` to insert
+    /// an already established connection
+    #[inline(always)]
+    pub fn set_status(&mut self, status: u32) -> Result {
+        termination_check!(unsafe {
+            to_result!(ffi::bpf_ct_set_status(self.ct, status))
+        })
+    }
+
+    /// Inserts the entry into the conntrack table, returning a reference to
+    /// it. `None` is returned if the insertion fails, e.g. because the
+    /// tuple already exists, in which case the entry is freed.
+    #[inline(always)]
+    pub fn insert(self) -> Option<Conntrack> {
+        let (ct, cleanup_idx) = (self.ct, self.cleanup_idx);
+        // The kfunc takes over the reference, freeing it on failure
+        mem::forget(self);
+
+        termination_check!({
+            // Put it before insertion so if it panics we will not free twice,
+            // the inserted entry is filled in within the same check
+            CleanupEntries::set_cleanup_arg(cleanup_idx, core::ptr::null_mut());
+
+            let ct = unsafe { ffi::bpf_ct_insert_entry(ct) };
+
+            if ct.is_null() {
+                CleanupEntries::deregister_cleanup(cleanup_idx);
+                None
+            } else {
+                CleanupEntries::set_cleanup_arg(cleanup_idx, ct as *mut ());
+                Some(Conntrack::new(ct, cleanup_idx, 0))
+            }
+        })
+    }
+}
+
+impl Drop for ConntrackInit {
+    /// Free the entry when the guard is out-of-scope
+    fn drop(&mut self) {
+        termination_check!({
+            // Put it before release so if it panics we will not release twice
+            CleanupEntries::deregister_cleanup(self.cleanup_idx);
+
+            unsafe { ffi::bpf_ct_release(self.ct) };
+        })
+    }
+}
+
+/// Unimplement Send and Sync
+impl !Send for ConntrackInit {}
+impl !Sync for ConntrackInit {}
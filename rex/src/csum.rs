@@ -0,0 +1,125 @@
+//! Internet checksum (RFC 1071) routines for packet programs
+//!
+//! All 16-bit words are taken in the byte order they are stored in the packet
+//! and the checksums are returned in that order as well, i.e. they can be
+//! compared with or written to the `check` field of a header directly. Since
+//! the one's complement sum is byte-order independent, no swapping is needed.
+
+/// Folds a 32-bit partial sum into 16 bits
+#[inline(always)]
+pub fn csum_fold_partial(sum: u32) -> u16 {
+    let sum = (sum & 0xffff) + (sum >> 16);
+    ((sum & 0xffff) + (sum >> 16)) as u16
+}
+
+/// Folds a 32-bit partial sum into the final 16-bit checksum
+#[inline(always)]
+pub fn csum_fold(sum: u32) -> u16 {
+    !csum_fold_partial(sum)
+}
+
+/// Adds two partial sums with end-around carry
+#[inline(always)]
+pub fn csum_add(sum: u32, addend: u32) -> u32 {
+    let (res, carry) = sum.overflowing_add(addend);
+    res + carry as u32
+}
+
+/// Subtracts a partial sum from another, i.e. adds its one's complement
+#[inline(always)]
+pub fn csum_sub(sum: u32, addend: u32) -> u32 {
+    csum_add(sum, !addend)
+}
+
+/// Adds `data` to the partial sum `sum`, a trailing odd byte is padded with
+/// zero as required by RFC 1071
+#[inline(always)]
+pub fn csum_partial(data: &[u8], sum: u32) -> u32 {
+    let mut sum = sum;
+    let mut chunks = data.chunks_exact(2);
+
+    for word in &mut chunks {
+        sum = csum_add(sum, u16::from_ne_bytes([word[0], word[1]]) as u32);
+    }
+
+    if let [last] = chunks.remainder() {
+        sum = csum_add(sum, u16::from_ne_bytes([*last, 0]) as u32);
+    }
+
+    sum
+}
+
+/// Updates the checksum `check` in place after a 16-bit field covered by it
+/// changed from `old` to `new` (RFC 1624, eqn. 3)
+#[inline(always)]
+pub fn csum_replace2(check: &mut u16, old: u16, new: u16) {
+    let sum = csum_add(!*check as u32, !old as u32);
+    *check = csum_fold(csum_add(sum, new as u32));
+}
+
+/// Updates the checksum `check` in place after a 32-bit field (e.g. an IPv4
+/// address) covered by it changed from `old` to `new`
+#[inline(always)]
+pub fn csum_replace4(check: &mut u16, old: u32, new: u32) {
+    let sum = csum_sub(!*check as u32, old);
+    *check = csum_fold(csum_add(sum, new));
+}
+
+/// Updates the checksum `check` in place after a 128-bit field (e.g. an IPv6
+/// address) covered by it changed from `old` to `new`
+#[inline(always)]
+pub fn csum_replace16(check: &mut u16, old: &[u8; 16], new: &[u8; 16]) {
+    let sum = csum_sub(!*check as u32, csum_partial(old, 0));
+    *check = csum_fold(csum_add(sum, csum_partial(new, 0)));
+}
+
+/// Partial sum of the IPv4 pseudo-header for a TCP or UDP checksum, `saddr`
+/// and `daddr` are as stored in the `iphdr` and `len` is the length of the
+/// L4 header plus payload in host byte order
+#[inline(always)]
+pub fn ipv4_pseudo_hdr_sum(saddr: u32, daddr: u32, proto: u8, len: u16) -> u32 {
+    let mut sum = csum_partial(&saddr.to_ne_bytes(), 0);
+    sum = csum_partial(&daddr.to_ne_bytes(), sum);
+    sum = csum_partial(&[0, proto], sum);
+    csum_partial(&len.to_be_bytes(), sum)
+}
+
+/// Partial sum of the IPv6 pseudo-header for a TCP, UDP or ICMPv6 checksum,
+/// `len` is the upper-layer packet length in host byte order
+#[inline(always)]
+pub fn ipv6_pseudo_hdr_sum(
+    saddr: &[u8; 16],
+    daddr: &[u8; 16],
+    proto: u8,
+    len: u32,
+) -> u32 {
+    let mut sum = csum_partial(saddr, 0);
+    sum = csum_partial(daddr, sum);
+    sum = csum_partial(&len.to_be_bytes(), sum);
+    csum_partial(&[0, 0, 0, proto], sum)
+}
+
+/// Computes the checksum of a TCP or UDP segment over IPv4. `l4` is the L4
+/// header plus payload, whose checksum field must be zeroed beforehand.
+///
+/// A zero UDP checksum means "no checksum", so UDP callers should transmit
+/// `0xffff` instead if this returns 0.
+#[inline(always)]
+pub fn ipv4_l4_csum(saddr: u32, daddr: u32, proto: u8, l4: &[u8]) -> u16 {
+    let sum = ipv4_pseudo_hdr_sum(saddr, daddr, proto, l4.len() as u16);
+    csum_fold(csum_partial(l4, sum))
+}
+
+/// Computes the checksum of a TCP, UDP or ICMPv6 packet over IPv6. `l4` is
+/// the L4 header plus payload, whose checksum field must be zeroed
+/// beforehand.
+#[inline(always)]
+pub fn ipv6_l4_csum(
+    saddr: &[u8; 16],
+    daddr: &[u8; 16],
+    proto: u8,
+    l4: &[u8],
+) -> u16 {
+    let sum = ipv6_pseudo_hdr_sum(saddr, daddr, proto, l4.len() as u32);
+    csum_fold(csum_partial(l4, sum))
+}
@@ -5,9 +5,14 @@
 use core::ffi::{c_uchar, VaList};
 
 use crate::bindings::linux::kernel::{
-    bpf_perf_event_data_kern, sk_buff, task_struct, xdp_buff, MAX_BPRINTF_BUF,
+    bpf_perf_event_data_kern, bpf_sk_lookup_kern, bpf_sock_ops_kern,
+    bpf_sysctl_kern, nf_conn, pt_regs, seq_file, sk_buff, sk_msg,
+    sk_reuseport_kern, sock, task_struct, tcp_sock, xdp_buff, MAX_BPRINTF_BUF,
 };
-use crate::bindings::uapi::linux::bpf::{bpf_perf_event_value, bpf_spin_lock};
+use crate::bindings::uapi::linux::bpf::{
+    bpf_fib_lookup, bpf_perf_event_value, bpf_sock_tuple, bpf_spin_lock,
+};
+use crate::conntrack::bpf_ct_opts;
 use crate::panic::{CleanupEntry, ENTRIES_SIZE};
 
 // Functions
@@ -61,6 +66,14 @@ unsafe extern "C" {
         unsafe_ptr: *const (),
     ) -> i64;
 
+    /// `long bpf_probe_read_user(void *dst, u32 size, const void __user
+    /// *unsafe_ptr)`
+    pub(crate) fn bpf_probe_read_user(
+        dst: *mut (),
+        size: u32,
+        unsafe_ptr: *const (),
+    ) -> i64;
+
     /// `u64 notrace ktime_get_mono_fast_ns(void)`
     pub(crate) fn ktime_get_mono_fast_ns() -> u64;
 
@@ -106,6 +119,15 @@ unsafe extern "C" {
     /// `asmlinkage void just_return_func(void)`
     pub(crate) fn just_return_func();
 
+    /// `u64 bpf_get_func_ip_kprobe(struct pt_regs *regs)`
+    pub(crate) fn bpf_get_func_ip_kprobe(regs: *const pt_regs) -> u64;
+
+    /// `u64 bpf_get_func_ip_kprobe_multi(struct pt_regs *regs)`
+    pub(crate) fn bpf_get_func_ip_kprobe_multi(regs: *const pt_regs) -> u64;
+
+    /// `u64 bpf_get_attach_cookie_trace(void *ctx)`
+    pub(crate) fn bpf_get_attach_cookie_trace(ctx: *mut ()) -> u64;
+
     /// `long bpf_get_stackid_pe(struct bpf_perf_event_data_kern *ctx, struct
     /// bpf_map *map, u64 flags)`
     ///
@@ -193,6 +215,37 @@ unsafe extern "C" {
     #[allow(improper_ctypes)]
     pub(crate) fn bpf_xdp_adjust_tail(xdp: *mut xdp_buff, offset: i32) -> i32;
 
+    /// `long bpf_xdp_adjust_meta(struct xdp_buff *xdp, int offset)`
+    ///
+    /// The compiler complains about some non-FFI safe type, but since the
+    /// kernel is using it fine it should be safe for an FFI call using C ABI
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_xdp_adjust_meta(xdp: *mut xdp_buff, offset: i32) -> i32;
+
+    /// `long bpf_xdp_load_bytes(struct xdp_buff *xdp, u32 offset, void *buf,
+    /// u32 len)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_xdp_load_bytes(
+        xdp: *mut xdp_buff,
+        offset: u32,
+        buf: *mut (),
+        len: u32,
+    ) -> i64;
+
+    /// `long bpf_xdp_store_bytes(struct xdp_buff *xdp, u32 offset, void *buf,
+    /// u32 len)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_xdp_store_bytes(
+        xdp: *mut xdp_buff,
+        offset: u32,
+        buf: *const (),
+        len: u32,
+    ) -> i64;
+
+    /// `u64 bpf_xdp_get_buff_len(struct xdp_buff *xdp)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_xdp_get_buff_len(xdp: *mut xdp_buff) -> u64;
+
     /// long bpf_clone_redirect(struct sk_buff *skb, u32 ifindex, u64 flags)
     ///
     /// The compiler complains about some non-FFI safe type, but since the
@@ -231,6 +284,387 @@ unsafe extern "C" {
     /// u64 bpf_ringbuf_query(void *ringbuf, u64 flags)
     pub(crate) fn bpf_ringbuf_query(ringbuf: *mut (), flags: u64) -> u64;
 
+    /// `long bpf_msg_redirect_map(struct sk_msg *msg, struct bpf_map *map, u32
+    /// key, u64 flags)`
+    ///
+    /// The compiler complains about some non-FFI safe type, but since the
+    /// kernel is using it fine it should be safe for an FFI call using C ABI
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_msg_redirect_map(
+        msg: *mut sk_msg,
+        map: *mut (),
+        key: u32,
+        flags: u64,
+    ) -> i64;
+
+    /// `long bpf_msg_redirect_hash(struct sk_msg *msg, struct bpf_map *map,
+    /// void *key, u64 flags)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_msg_redirect_hash(
+        msg: *mut sk_msg,
+        map: *mut (),
+        key: *const (),
+        flags: u64,
+    ) -> i64;
+
+    /// `long bpf_msg_apply_bytes(struct sk_msg *msg, u32 bytes)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_msg_apply_bytes(msg: *mut sk_msg, bytes: u32) -> i64;
+
+    /// `long bpf_msg_cork_bytes(struct sk_msg *msg, u32 bytes)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_msg_cork_bytes(msg: *mut sk_msg, bytes: u32) -> i64;
+
+    /// `long bpf_sk_redirect_map(struct sk_buff *skb, struct bpf_map *map, u32
+    /// key, u64 flags)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_sk_redirect_map(
+        skb: *mut sk_buff,
+        map: *mut (),
+        key: u32,
+        flags: u64,
+    ) -> i64;
+
+    /// `long bpf_sk_redirect_hash(struct sk_buff *skb, struct bpf_map *map,
+    /// void *key, u64 flags)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_sk_redirect_hash(
+        skb: *mut sk_buff,
+        map: *mut (),
+        key: *const (),
+        flags: u64,
+    ) -> i64;
+
+    /// `u32 tcp_slow_start(struct tcp_sock *tp, u32 acked)`
+    ///
+    /// The compiler complains about some non-FFI safe type, but since the
+    /// kernel is using it fine it should be safe for an FFI call using C ABI
+    #[allow(improper_ctypes)]
+    pub(crate) fn tcp_slow_start(tp: *mut tcp_sock, acked: u32) -> u32;
+
+    /// `void tcp_cong_avoid_ai(struct tcp_sock *tp, u32 w, u32 acked)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn tcp_cong_avoid_ai(tp: *mut tcp_sock, w: u32, acked: u32);
+
+    /// `u32 tcp_reno_ssthresh(struct sock *sk)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn tcp_reno_ssthresh(sk: *mut sock) -> u32;
+
+    /// `void tcp_reno_cong_avoid(struct sock *sk, u32 ack, u32 acked)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn tcp_reno_cong_avoid(sk: *mut sock, ack: u32, acked: u32);
+
+    /// `u32 tcp_reno_undo_cwnd(struct sock *sk)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn tcp_reno_undo_cwnd(sk: *mut sock) -> u32;
+
+    /// `long bpf_sysctl_get_name(struct bpf_sysctl_kern *ctx, char *buf,
+    /// size_t buf_len, u64 flags)`
+    ///
+    /// The compiler complains about some non-FFI safe type, but since the
+    /// kernel is using it fine it should be safe for an FFI call using C ABI
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_sysctl_get_name(
+        ctx: *mut bpf_sysctl_kern,
+        buf: *mut c_uchar,
+        buf_len: usize,
+        flags: u64,
+    ) -> i64;
+
+    /// `long bpf_sysctl_get_current_value(struct bpf_sysctl_kern *ctx, char
+    /// *buf, size_t buf_len)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_sysctl_get_current_value(
+        ctx: *mut bpf_sysctl_kern,
+        buf: *mut c_uchar,
+        buf_len: usize,
+    ) -> i64;
+
+    /// `long bpf_sysctl_get_new_value(struct bpf_sysctl_kern *ctx, char *buf,
+    /// size_t buf_len)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_sysctl_get_new_value(
+        ctx: *mut bpf_sysctl_kern,
+        buf: *mut c_uchar,
+        buf_len: usize,
+    ) -> i64;
+
+    /// `long bpf_sysctl_set_new_value(struct bpf_sysctl_kern *ctx, const char
+    /// *buf, size_t buf_len)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_sysctl_set_new_value(
+        ctx: *mut bpf_sysctl_kern,
+        buf: *const c_uchar,
+        buf_len: usize,
+    ) -> i64;
+
+    /// `long bpf_sock_ops_setsockopt(struct bpf_sock_ops_kern *bpf_sock, int
+    /// level, int optname, char *optval, int optlen)`
+    ///
+    /// The compiler complains about some non-FFI safe type, but since the
+    /// kernel is using it fine it should be safe for an FFI call using C ABI
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_sock_ops_setsockopt(
+        bpf_sock: *mut bpf_sock_ops_kern,
+        level: i32,
+        optname: i32,
+        optval: *mut (),
+        optlen: i32,
+    ) -> i64;
+
+    /// `long bpf_sock_ops_getsockopt(struct bpf_sock_ops_kern *bpf_sock, int
+    /// level, int optname, char *optval, int optlen)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_sock_ops_getsockopt(
+        bpf_sock: *mut bpf_sock_ops_kern,
+        level: i32,
+        optname: i32,
+        optval: *mut (),
+        optlen: i32,
+    ) -> i64;
+
+    /// `long bpf_sock_ops_cb_flags_set(struct bpf_sock_ops_kern *bpf_sock, int
+    /// argval)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_sock_ops_cb_flags_set(
+        bpf_sock: *mut bpf_sock_ops_kern,
+        argval: i32,
+    ) -> i64;
+
+    /// `long bpf_sk_lookup_assign(struct bpf_sk_lookup_kern *ctx, struct sock
+    /// *sk, u64 flags)`
+    ///
+    /// The compiler complains about some non-FFI safe type, but since the
+    /// kernel is using it fine it should be safe for an FFI call using C ABI
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_sk_lookup_assign(
+        ctx: *mut bpf_sk_lookup_kern,
+        sk: *mut sock,
+        flags: u64,
+    ) -> i64;
+
+    /// `long bpf_sk_release(struct sock *sk)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_sk_release(sk: *mut sock) -> i64;
+
+    /// `long sk_select_reuseport(struct sk_reuseport_kern *reuse_kern, struct
+    /// bpf_map *map, void *key, u32 flags)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn sk_select_reuseport(
+        reuse_kern: *mut sk_reuseport_kern,
+        map: *mut (),
+        key: *const (),
+        flags: u32,
+    ) -> i64;
+
+    /// `long bpf_seq_write(struct seq_file *m, const void *data, u32 len)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_seq_write(
+        m: *mut seq_file,
+        data: *const (),
+        len: u32,
+    ) -> i64;
+
+    /// `s64 bpf_csum_diff(__be32 *from, u32 from_size, __be32 *to, u32
+    /// to_size, __wsum seed)`
+    pub(crate) fn bpf_csum_diff(
+        from: *const u32,
+        from_size: u32,
+        to: *const u32,
+        to_size: u32,
+        seed: u32,
+    ) -> i64;
+
+    /// `long bpf_l3_csum_replace(struct sk_buff *skb, u32 offset, u64 from,
+    /// u64 to, u64 size)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_l3_csum_replace(
+        skb: *mut sk_buff,
+        offset: u32,
+        from: u64,
+        to: u64,
+        size: u64,
+    ) -> i64;
+
+    /// `long bpf_l4_csum_replace(struct sk_buff *skb, u32 offset, u64 from,
+    /// u64 to, u64 flags)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_l4_csum_replace(
+        skb: *mut sk_buff,
+        offset: u32,
+        from: u64,
+        to: u64,
+        flags: u64,
+    ) -> i64;
+
+    /// `long bpf_xdp_fib_lookup(struct xdp_buff *ctx, struct bpf_fib_lookup
+    /// *params, int plen, u32 flags)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_xdp_fib_lookup(
+        ctx: *mut xdp_buff,
+        params: *mut bpf_fib_lookup,
+        plen: i32,
+        flags: u32,
+    ) -> i64;
+
+    /// `long bpf_skb_fib_lookup(struct sk_buff *skb, struct bpf_fib_lookup
+    /// *params, int plen, u32 flags)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_skb_fib_lookup(
+        skb: *mut sk_buff,
+        params: *mut bpf_fib_lookup,
+        plen: i32,
+        flags: u32,
+    ) -> i64;
+
+    /// `struct sock *bpf_sk_lookup_tcp(struct sk_buff *skb, struct
+    /// bpf_sock_tuple *tuple, u32 len, u64 netns_id, u64 flags)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_sk_lookup_tcp(
+        skb: *mut sk_buff,
+        tuple: *mut bpf_sock_tuple,
+        len: u32,
+        netns_id: u64,
+        flags: u64,
+    ) -> *mut sock;
+
+    /// `struct sock *bpf_sk_lookup_udp(struct sk_buff *skb, struct
+    /// bpf_sock_tuple *tuple, u32 len, u64 netns_id, u64 flags)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_sk_lookup_udp(
+        skb: *mut sk_buff,
+        tuple: *mut bpf_sock_tuple,
+        len: u32,
+        netns_id: u64,
+        flags: u64,
+    ) -> *mut sock;
+
+    /// `struct sock *bpf_skc_lookup_tcp(struct sk_buff *skb, struct
+    /// bpf_sock_tuple *tuple, u32 len, u64 netns_id, u64 flags)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_skc_lookup_tcp(
+        skb: *mut sk_buff,
+        tuple: *mut bpf_sock_tuple,
+        len: u32,
+        netns_id: u64,
+        flags: u64,
+    ) -> *mut sock;
+
+    /// `struct sock *bpf_xdp_sk_lookup_tcp(struct xdp_buff *ctx, struct
+    /// bpf_sock_tuple *tuple, u32 len, u32 netns_id, u64 flags)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_xdp_sk_lookup_tcp(
+        ctx: *mut xdp_buff,
+        tuple: *mut bpf_sock_tuple,
+        len: u32,
+        netns_id: u32,
+        flags: u64,
+    ) -> *mut sock;
+
+    /// `struct sock *bpf_xdp_sk_lookup_udp(struct xdp_buff *ctx, struct
+    /// bpf_sock_tuple *tuple, u32 len, u32 netns_id, u64 flags)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_xdp_sk_lookup_udp(
+        ctx: *mut xdp_buff,
+        tuple: *mut bpf_sock_tuple,
+        len: u32,
+        netns_id: u32,
+        flags: u64,
+    ) -> *mut sock;
+
+    /// `struct sock *bpf_xdp_skc_lookup_tcp(struct xdp_buff *ctx, struct
+    /// bpf_sock_tuple *tuple, u32 len, u32 netns_id, u64 flags)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_xdp_skc_lookup_tcp(
+        ctx: *mut xdp_buff,
+        tuple: *mut bpf_sock_tuple,
+        len: u32,
+        netns_id: u32,
+        flags: u64,
+    ) -> *mut sock;
+
+    /// `struct nf_conn *bpf_xdp_ct_lookup(struct xdp_md *xdp_ctx, struct
+    /// bpf_sock_tuple *bpf_tuple, u32 tuple__sz, struct bpf_ct_opts *opts,
+    /// u32 opts__sz)`
+    ///
+    /// `xdp_ctx` is the `struct xdp_buff` in the kernel
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_xdp_ct_lookup(
+        xdp_ctx: *mut xdp_buff,
+        bpf_tuple: *mut bpf_sock_tuple,
+        tuple_sz: u32,
+        opts: *mut bpf_ct_opts,
+        opts_sz: u32,
+    ) -> *mut nf_conn;
+
+    /// `struct nf_conn___init *bpf_xdp_ct_alloc(struct xdp_md *xdp_ctx,
+    /// struct bpf_sock_tuple *bpf_tuple, u32 tuple__sz, struct bpf_ct_opts
+    /// *opts, u32 opts__sz)`
+    ///
+    /// `struct nf_conn___init` only wraps a `struct nf_conn`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_xdp_ct_alloc(
+        xdp_ctx: *mut xdp_buff,
+        bpf_tuple: *mut bpf_sock_tuple,
+        tuple_sz: u32,
+        opts: *mut bpf_ct_opts,
+        opts_sz: u32,
+    ) -> *mut nf_conn;
+
+    /// `struct nf_conn *bpf_skb_ct_lookup(struct __sk_buff *skb_ctx, struct
+    /// bpf_sock_tuple *bpf_tuple, u32 tuple__sz, struct bpf_ct_opts *opts,
+    /// u32 opts__sz)`
+    ///
+    /// `skb_ctx` is the `struct sk_buff` in the kernel
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_skb_ct_lookup(
+        skb_ctx: *mut sk_buff,
+        bpf_tuple: *mut bpf_sock_tuple,
+        tuple_sz: u32,
+        opts: *mut bpf_ct_opts,
+        opts_sz: u32,
+    ) -> *mut nf_conn;
+
+    /// `struct nf_conn___init *bpf_skb_ct_alloc(struct __sk_buff *skb_ctx,
+    /// struct bpf_sock_tuple *bpf_tuple, u32 tuple__sz, struct bpf_ct_opts
+    /// *opts, u32 opts__sz)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_skb_ct_alloc(
+        skb_ctx: *mut sk_buff,
+        bpf_tuple: *mut bpf_sock_tuple,
+        tuple_sz: u32,
+        opts: *mut bpf_ct_opts,
+        opts_sz: u32,
+    ) -> *mut nf_conn;
+
+    /// `struct nf_conn *bpf_ct_insert_entry(struct nf_conn___init *nfct_i)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_ct_insert_entry(nfct_i: *mut nf_conn) -> *mut nf_conn;
+
+    /// `void bpf_ct_release(struct nf_conn *nfct)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_ct_release(nfct: *mut nf_conn);
+
+    /// `void bpf_ct_set_timeout(struct nf_conn___init *nfct, u32 timeout)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_ct_set_timeout(nfct: *mut nf_conn, timeout: u32);
+
+    /// `int bpf_ct_change_timeout(struct nf_conn *nfct, u32 timeout)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_ct_change_timeout(
+        nfct: *mut nf_conn,
+        timeout: u32,
+    ) -> i32;
+
+    /// `int bpf_ct_set_status(const struct nf_conn___init *nfct, u32
+    /// status)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_ct_set_status(nfct: *mut nf_conn, status: u32) -> i32;
+
+    /// `int bpf_ct_change_status(struct nf_conn *nfct, u32 status)`
+    #[allow(improper_ctypes)]
+    pub(crate) fn bpf_ct_change_status(nfct: *mut nf_conn, status: u32) -> i32;
+
     /// void rex_trace_printk(void)
     pub(crate) fn rex_trace_printk();
 }
@@ -0,0 +1,215 @@
+use core::ffi::c_int;
+use core::mem::size_of;
+
+use crate::base_helper::termination_check;
+use crate::bindings::uapi::linux::bpf::{
+    bpf_fib_lookup, BPF_FIB_LKUP_RET_BLACKHOLE, BPF_FIB_LKUP_RET_FRAG_NEEDED,
+    BPF_FIB_LKUP_RET_FWD_DISABLED, BPF_FIB_LKUP_RET_NOT_FWDED,
+    BPF_FIB_LKUP_RET_NO_NEIGH, BPF_FIB_LKUP_RET_NO_SRC_ADDR,
+    BPF_FIB_LKUP_RET_PROHIBIT, BPF_FIB_LKUP_RET_SUCCESS,
+    BPF_FIB_LKUP_RET_UNREACHABLE, BPF_FIB_LKUP_RET_UNSUPP_LWT,
+};
+// expose the following constants to the user
+pub use crate::bindings::uapi::linux::bpf::{
+    BPF_FIB_LOOKUP_DIRECT, BPF_FIB_LOOKUP_OUTPUT, BPF_FIB_LOOKUP_SKIP_NEIGH,
+    BPF_FIB_LOOKUP_SRC,
+};
+use crate::linux::errno::EINVAL;
+use crate::utils::{u16be, u32be};
+
+// From include/linux/socket.h, which is not part of the uapi
+const AF_INET: u8 = 2;
+const AF_INET6: u8 = 10;
+
+/// Input of [`bpf_fib_lookup`](crate::xdp::xdp::bpf_fib_lookup), usually
+/// filled from the headers of the packet being forwarded
+#[derive(Debug, Copy, Clone)]
+pub enum FibLookupParams {
+    Ipv4 {
+        src: u32be,
+        dst: u32be,
+        tos: u8,
+        /// `IPPROTO_*`, only needed for policy routing on the ports
+        l4_protocol: u8,
+        sport: u16be,
+        dport: u16be,
+        /// Length of the packet from the IP header (i.e. `tot_len`), a
+        /// non-zero value enables the MTU check
+        tot_len: u16,
+        /// Ingress device, or the egress device with
+        /// `BPF_FIB_LOOKUP_OUTPUT`
+        ifindex: u32,
+    },
+    Ipv6 {
+        src: [u8; 16],
+        dst: [u8; 16],
+        /// Flow label and priority
+        flowinfo: u32be,
+        /// `IPPROTO_*`, only needed for policy routing on the ports
+        l4_protocol: u8,
+        sport: u16be,
+        dport: u16be,
+        /// Length of the packet from the IP header, a non-zero value enables
+        /// the MTU check
+        tot_len: u16,
+        /// Ingress device, or the egress device with
+        /// `BPF_FIB_LOOKUP_OUTPUT`
+        ifindex: u32,
+    },
+}
+
+/// Where a packet should be forwarded to
+#[derive(Debug, Copy, Clone)]
+pub struct FibNexthop {
+    /// Egress device, e.g. for `bpf_redirect`
+    pub ifindex: u32,
+    /// MAC address of the egress device, i.e. the new source MAC
+    pub smac: [u8; 6],
+    /// MAC address of the next hop, i.e. the new destination MAC
+    pub dmac: [u8; 6],
+    /// MTU of the egress device
+    pub mtu: u16,
+}
+
+/// Result of [`bpf_fib_lookup`](crate::xdp::xdp::bpf_fib_lookup), the
+/// `BPF_FIB_LKUP_RET_*` codes
+#[derive(Debug, Copy, Clone)]
+pub enum FibLookupResult {
+    /// The packet can be forwarded to the next hop
+    Success(FibNexthop),
+    /// The destination is blackholed, the packet can be dropped
+    Blackhole,
+    /// The destination is unreachable, the packet can be dropped
+    Unreachable,
+    /// Forwarding to the destination is prohibited, the packet can be
+    /// dropped
+    Prohibit,
+    /// The packet is not forwarded, e.g. it is destined to the local host
+    NotForwarded,
+    /// Forwarding is disabled on the ingress device
+    ForwardingDisabled,
+    /// Forwarding requires an encapsulation
+    UnsupportedLwt,
+    /// There is no neighbor entry for the next hop on the egress device
+    NoNeighbor { ifindex: u32 },
+    /// The packet exceeds the MTU of the egress device
+    FragNeeded { mtu: u16 },
+    /// No source address could be derived with `BPF_FIB_LOOKUP_SRC`
+    NoSrcAddr,
+}
+
+impl FibLookupParams {
+    fn to_raw(self) -> bpf_fib_lookup {
+        let mut raw = bpf_fib_lookup::default();
+
+        match self {
+            FibLookupParams::Ipv4 {
+                src,
+                dst,
+                tos,
+                l4_protocol,
+                sport,
+                dport,
+                tot_len,
+                ifindex,
+            } => {
+                raw.family = AF_INET;
+                raw.l4_protocol = l4_protocol;
+                raw.sport = sport.be_bits();
+                raw.dport = dport.be_bits();
+                raw.__bindgen_anon_1.tot_len = tot_len;
+                raw.ifindex = ifindex;
+                raw.__bindgen_anon_2.tos = tos;
+                raw.__bindgen_anon_3.ipv4_src = src.be_bits();
+                raw.__bindgen_anon_4.ipv4_dst = dst.be_bits();
+            }
+            FibLookupParams::Ipv6 {
+                src,
+                dst,
+                flowinfo,
+                l4_protocol,
+                sport,
+                dport,
+                tot_len,
+                ifindex,
+            } => {
+                raw.family = AF_INET6;
+                raw.l4_protocol = l4_protocol;
+                raw.sport = sport.be_bits();
+                raw.dport = dport.be_bits();
+                raw.__bindgen_anon_1.tot_len = tot_len;
+                raw.ifindex = ifindex;
+                raw.__bindgen_anon_2.flowinfo = flowinfo.be_bits();
+                raw.__bindgen_anon_3.ipv6_src = addr_to_words(&src);
+                raw.__bindgen_anon_4.ipv6_dst = addr_to_words(&dst);
+            }
+        }
+
+        raw
+    }
+}
+
+/// Converts an IPv6 address to the `__u32[4]` used by the uapi structs (e.g.
+/// `struct bpf_fib_lookup`), keeping the network order
+#[inline(always)]
+pub(crate) fn addr_to_words(addr: &[u8; 16]) -> [u32; 4] {
+    let mut words = [0u32; 4];
+    for (i, word) in words.iter_mut().enumerate() {
+        *word = u32::from_ne_bytes([
+            addr[i * 4],
+            addr[i * 4 + 1],
+            addr[i * 4 + 2],
+            addr[i * 4 + 3],
+        ]);
+    }
+    words
+}
+
+/// Shared implementation of `bpf_fib_lookup` for `xdp` and `sched_cls`,
+/// `lookup_fn` runs the helper of the program type (`bpf_xdp_fib_lookup` or
+/// `bpf_skb_fib_lookup`) with the raw parameters and their size
+#[inline(always)]
+pub(crate) fn fib_lookup<F>(
+    params: &FibLookupParams,
+    lookup_fn: F,
+) -> Result<FibLookupResult, c_int>
+where
+    F: FnOnce(*mut bpf_fib_lookup, c_int) -> i64,
+{
+    let mut raw = params.to_raw();
+
+    let ret = termination_check!(lookup_fn(
+        &mut raw,
+        size_of::<bpf_fib_lookup>() as c_int
+    ));
+
+    if ret < 0 {
+        return Err(ret as c_int);
+    }
+
+    // mtu_result shares the storage with tot_len
+    let mtu = unsafe { raw.__bindgen_anon_1.mtu_result };
+
+    let res = match ret as u32 {
+        BPF_FIB_LKUP_RET_SUCCESS => FibLookupResult::Success(FibNexthop {
+            ifindex: raw.ifindex,
+            smac: raw.smac,
+            dmac: raw.dmac,
+            mtu,
+        }),
+        BPF_FIB_LKUP_RET_BLACKHOLE => FibLookupResult::Blackhole,
+        BPF_FIB_LKUP_RET_UNREACHABLE => FibLookupResult::Unreachable,
+        BPF_FIB_LKUP_RET_PROHIBIT => FibLookupResult::Prohibit,
+        BPF_FIB_LKUP_RET_NOT_FWDED => FibLookupResult::NotForwarded,
+        BPF_FIB_LKUP_RET_FWD_DISABLED => FibLookupResult::ForwardingDisabled,
+        BPF_FIB_LKUP_RET_UNSUPP_LWT => FibLookupResult::UnsupportedLwt,
+        BPF_FIB_LKUP_RET_NO_NEIGH => FibLookupResult::NoNeighbor {
+            ifindex: raw.ifindex,
+        },
+        BPF_FIB_LKUP_RET_FRAG_NEEDED => FibLookupResult::FragNeeded { mtu },
+        BPF_FIB_LKUP_RET_NO_SRC_ADDR => FibLookupResult::NoSrcAddr,
+        _ => return Err(-(EINVAL as c_int)),
+    };
+
+    Ok(res)
+}
@@ -0,0 +1,44 @@
+use crate::bindings::linux::kernel::file;
+
+/// Read-only wrapper of the kernel `struct file`
+pub struct File {
+    // struct file * passed to the program is pinned by the caller and
+    // outlives the program execution
+    pub(crate) kptr: &'static file,
+}
+
+impl File {
+    #[inline(always)]
+    pub(crate) const fn new(kp: &'static file) -> Self {
+        Self { kptr: kp }
+    }
+
+    /// Flags passed to `open(2)`, e.g. `O_RDONLY`
+    #[inline(always)]
+    pub fn flags(&self) -> u32 {
+        self.kptr.f_flags
+    }
+
+    /// Access mode of the file, e.g. `FMODE_READ`
+    #[inline(always)]
+    pub fn mode(&self) -> u32 {
+        self.kptr.f_mode
+    }
+
+    #[inline(always)]
+    pub fn pos(&self) -> i64 {
+        self.kptr.f_pos
+    }
+
+    /// Inode number of the file
+    #[inline(always)]
+    pub fn ino(&self) -> u64 {
+        unsafe { (*self.kptr.f_inode).i_ino }
+    }
+
+    /// Owner of the inode
+    #[inline(always)]
+    pub fn uid(&self) -> u32 {
+        unsafe { (*self.kptr.f_inode).i_uid.val }
+    }
+}
@@ -0,0 +1,153 @@
+use core::ffi::c_uchar;
+use core::slice;
+
+pub use crate::bindings::linux::kernel::bpf_flow_keys;
+use crate::bindings::linux::kernel::{
+    bpf_flow_dissector as bpf_flow_dissector_kern, icmp6hdr, icmphdr, iphdr,
+    ipv6hdr, tcphdr, udphdr,
+};
+use crate::bindings::uapi::linux::bpf::bpf_map_type;
+// expose the following constants to the user
+pub use crate::bindings::uapi::linux::bpf::{
+    BPF_DROP, BPF_FLOW_DISSECTOR_CONTINUE, BPF_FLOW_DISSECTOR_F_PARSE_1ST_FRAG,
+    BPF_FLOW_DISSECTOR_F_STOP_AT_ENCAP,
+    BPF_FLOW_DISSECTOR_F_STOP_AT_FLOW_LABEL, BPF_OK,
+};
+use crate::prog_type::rex_prog;
+use crate::utils::*;
+
+pub struct bpf_flow_dissector<'a> {
+    /// Packet data, the network header starts at `flow_keys.nhoff`.
+    /// The packet cannot be modified by the program.
+    pub data_slice: &'a [c_uchar],
+    /// Dissection result to be filled by the program, `nhoff` and `thoff`
+    /// are initialized to the offset of the network header
+    pub flow_keys: &'a mut bpf_flow_keys,
+}
+
+impl bpf_flow_dissector<'_> {
+    /// Input flags of the dissection, see `BPF_FLOW_DISSECTOR_F_*`
+    #[inline(always)]
+    pub fn flags(&self) -> u32 {
+        self.flow_keys.flags
+    }
+
+    /// Addresses are in network byte order
+    #[inline(always)]
+    pub fn set_ipv4_addrs(&mut self, src: u32, dst: u32) {
+        let addrs =
+            unsafe { &mut self.flow_keys.__bindgen_anon_1.__bindgen_anon_1 };
+        addrs.ipv4_src = src;
+        addrs.ipv4_dst = dst;
+    }
+
+    /// Addresses are in network byte order
+    #[inline(always)]
+    pub fn set_ipv6_addrs(&mut self, src: &[u32; 4], dst: &[u32; 4]) {
+        let addrs =
+            unsafe { &mut self.flow_keys.__bindgen_anon_1.__bindgen_anon_2 };
+        addrs.ipv6_src = *src;
+        addrs.ipv6_dst = *dst;
+    }
+}
+
+/// prog_fn should have &Self as its first argument
+///
+/// The program returns `Ok(BPF_OK)` after filling in `flow_keys`,
+/// `Ok(BPF_DROP)` to stop the dissection and
+/// `Ok(BPF_FLOW_DISSECTOR_CONTINUE)` to fall back to the kernel's built-in
+/// dissector. Returning an `Err` is treated the same as `BPF_DROP`.
+#[repr(C)]
+pub struct flow_dissector {
+    prog: fn(&Self, &mut bpf_flow_dissector) -> Result,
+}
+
+impl flow_dissector {
+    crate::base_helper::base_helper_defs!();
+
+    /// Network header at `flow_keys.nhoff`, `None` if it does not fit in the
+    /// packet
+    #[inline(always)]
+    pub fn ip_header<'b>(
+        &self,
+        ctx: &'b bpf_flow_dissector,
+    ) -> Option<Aligned<'b, iphdr>> {
+        convert_slice_at_to_struct(ctx.data_slice, ctx.flow_keys.nhoff as usize)
+    }
+
+    /// Network header at `flow_keys.nhoff`, `None` if it does not fit in the
+    /// packet
+    #[inline(always)]
+    pub fn ipv6_header<'b>(
+        &self,
+        ctx: &'b bpf_flow_dissector,
+    ) -> Option<Aligned<'b, ipv6hdr>> {
+        convert_slice_at_to_struct(ctx.data_slice, ctx.flow_keys.nhoff as usize)
+    }
+
+    // The transport headers are located at `flow_keys.thoff`, the program is
+    // expected to update `thoff` after parsing the network header
+    #[inline(always)]
+    pub fn tcp_header<'b>(
+        &self,
+        ctx: &'b bpf_flow_dissector,
+    ) -> Option<Aligned<'b, tcphdr>> {
+        convert_slice_at_to_struct(ctx.data_slice, ctx.flow_keys.thoff as usize)
+    }
+
+    #[inline(always)]
+    pub fn udp_header<'b>(
+        &self,
+        ctx: &'b bpf_flow_dissector,
+    ) -> Option<Aligned<'b, udphdr>> {
+        convert_slice_at_to_struct(ctx.data_slice, ctx.flow_keys.thoff as usize)
+    }
+
+    #[inline(always)]
+    pub fn icmp_header<'b>(
+        &self,
+        ctx: &'b bpf_flow_dissector,
+    ) -> Option<Aligned<'b, icmphdr>> {
+        convert_slice_at_to_struct(ctx.data_slice, ctx.flow_keys.thoff as usize)
+    }
+
+    #[inline(always)]
+    pub fn icmp6_header<'b>(
+        &self,
+        ctx: &'b bpf_flow_dissector,
+    ) -> Option<Aligned<'b, icmp6hdr>> {
+        convert_slice_at_to_struct(ctx.data_slice, ctx.flow_keys.thoff as usize)
+    }
+
+    pub const unsafe fn new(
+        f: fn(&flow_dissector, &mut bpf_flow_dissector) -> Result,
+    ) -> flow_dissector {
+        Self { prog: f }
+    }
+
+    #[inline(always)]
+    fn convert_ctx(&self, ctx: *mut ()) -> bpf_flow_dissector {
+        let kptr = unsafe { &mut *(ctx as *mut bpf_flow_dissector_kern) };
+
+        // NOTE: skb can be NULL when the dissector is invoked on a raw
+        // buffer (e.g. eth_get_headlen), data and data_end are always valid
+        let data_length = kptr.data_end as usize - kptr.data as usize;
+        let data_slice = unsafe {
+            slice::from_raw_parts(kptr.data as *const c_uchar, data_length)
+        };
+        let flow_keys = unsafe { &mut *kptr.flow_keys };
+
+        bpf_flow_dissector {
+            data_slice,
+            flow_keys,
+        }
+    }
+}
+
+impl rex_prog for flow_dissector {
+    fn prog_run(&self, ctx: *mut ()) -> u32 {
+        let mut newctx = self.convert_ctx(ctx);
+        // stop the dissection if error
+        ((self.prog)(self, &mut newctx)).unwrap_or(BPF_DROP as i32) as u32
+    }
+}
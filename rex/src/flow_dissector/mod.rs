@@ -0,0 +1,3 @@
+mod flow_dissector_impl;
+
+pub use flow_dissector_impl::*;
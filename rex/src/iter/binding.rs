@@ -0,0 +1,180 @@
+use core::marker::PhantomData;
+use core::mem;
+
+use crate::bindings::linux::kernel::{
+    bpf_iter_meta, bpf_map, sock_common, task_struct,
+};
+use crate::task_struct::TaskStruct;
+use crate::utils::{u16be, u32be, NoRef};
+
+// The context of an iterator program is a struct whose first member is the
+// iterator meta, followed by the object being visited. The object is NULL on
+// the last invocation, after all objects have been visited.
+// NOTE: these structs are defined in the kernel source files, not in the
+// headers, so they need to be kept in sync manually
+
+/// `struct bpf_iter__task`
+#[repr(C)]
+pub struct TaskIterCtx {
+    pub(crate) meta: *mut bpf_iter_meta,
+    task: *const task_struct,
+}
+
+impl TaskIterCtx {
+    /// The task being visited, `None` after the last task
+    #[inline(always)]
+    pub fn task(&self) -> Option<TaskStruct> {
+        if self.task.is_null() {
+            None
+        } else {
+            Some(TaskStruct::new(unsafe { &*self.task }))
+        }
+    }
+}
+
+/// `struct bpf_iter__bpf_map_elem`
+///
+/// `K` and `V` are the key and value types of the map the iterator is
+/// attached to, the element is only exposed if their sizes match the ones of
+/// the map. For per-cpu maps the value is the one of the first CPU.
+#[repr(C)]
+pub struct BpfMapElemIterCtx<K, V>
+where
+    K: Copy + NoRef,
+    V: Copy + NoRef,
+{
+    pub(crate) meta: *mut bpf_iter_meta,
+    map: *const bpf_map,
+    key: *const K,
+    value: *mut V,
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K, V> BpfMapElemIterCtx<K, V>
+where
+    K: Copy + NoRef,
+    V: Copy + NoRef,
+{
+    /// Whether `K` and `V` have the key and value sizes of the map
+    #[inline(always)]
+    fn layout_matches(&self) -> bool {
+        // Keys and values are stored 8-byte aligned by the kernel
+        const {
+            assert!(mem::align_of::<K>() <= mem::align_of::<u64>());
+            assert!(mem::align_of::<V>() <= mem::align_of::<u64>());
+        }
+        match unsafe { self.map.as_ref() } {
+            Some(map) => {
+                map.key_size as usize == mem::size_of::<K>() &&
+                    map.value_size as usize == mem::size_of::<V>()
+            }
+            None => false,
+        }
+    }
+
+    /// Key of the element being visited, `None` after the last element
+    #[inline(always)]
+    pub fn key(&self) -> Option<&K> {
+        if !self.layout_matches() {
+            return None;
+        }
+        unsafe { self.key.as_ref() }
+    }
+
+    /// Value of the element being visited, `None` after the last element
+    #[inline(always)]
+    pub fn value(&self) -> Option<&V> {
+        if !self.layout_matches() {
+            return None;
+        }
+        unsafe { self.value.as_ref() }
+    }
+
+    /// The value can be updated in place
+    #[inline(always)]
+    pub fn value_mut(&mut self) -> Option<&mut V> {
+        if !self.layout_matches() {
+            return None;
+        }
+        unsafe { self.value.as_mut() }
+    }
+}
+
+/// `struct bpf_iter__tcp`
+#[repr(C)]
+pub struct TcpIterCtx {
+    pub(crate) meta: *mut bpf_iter_meta,
+    sk_common: *const sock_common,
+    uid: u32,
+}
+
+impl TcpIterCtx {
+    /// The socket being visited, `None` after the last socket. The socket
+    /// can be a full socket, a request socket or a time-wait socket.
+    #[inline(always)]
+    pub fn sk(&self) -> Option<SockCommon<'_>> {
+        unsafe { self.sk_common.as_ref() }.map(|kptr| SockCommon { kptr })
+    }
+
+    /// Owner of the socket, in the user namespace of the reader
+    #[inline(always)]
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+}
+
+/// The fields shared by all kinds of sockets
+pub struct SockCommon<'a> {
+    kptr: &'a sock_common,
+}
+
+impl<'a> SockCommon<'a> {
+    #[inline(always)]
+    pub(crate) fn new(kptr: &'a sock_common) -> Self {
+        Self { kptr }
+    }
+
+    #[inline(always)]
+    pub fn family(&self) -> u16 {
+        self.kptr.skc_family
+    }
+
+    /// TCP state of the socket, e.g. `TCP_ESTABLISHED`
+    #[inline(always)]
+    pub fn state(&self) -> u8 {
+        self.kptr.skc_state
+    }
+
+    #[inline(always)]
+    pub fn remote_ip4(&self) -> u32be {
+        u32be(unsafe { self.kptr.__bindgen_anon_1.__bindgen_anon_1.skc_daddr })
+    }
+
+    #[inline(always)]
+    pub fn local_ip4(&self) -> u32be {
+        u32be(unsafe {
+            self.kptr.__bindgen_anon_1.__bindgen_anon_1.skc_rcv_saddr
+        })
+    }
+
+    #[inline(always)]
+    pub fn remote_ip6(&self) -> [u32; 4] {
+        unsafe { self.kptr.skc_v6_daddr.in6_u.u6_addr32 }
+    }
+
+    #[inline(always)]
+    pub fn local_ip6(&self) -> [u32; 4] {
+        unsafe { self.kptr.skc_v6_rcv_saddr.in6_u.u6_addr32 }
+    }
+
+    #[inline(always)]
+    pub fn remote_port(&self) -> u16be {
+        u16be(unsafe { self.kptr.__bindgen_anon_3.__bindgen_anon_1.skc_dport })
+    }
+
+    /// In host byte order, unlike [`Self::remote_port`]
+    #[inline(always)]
+    pub fn local_port(&self) -> u16 {
+        unsafe { self.kptr.__bindgen_anon_3.__bindgen_anon_1.skc_num }
+    }
+}
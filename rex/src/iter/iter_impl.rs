@@ -0,0 +1,124 @@
+use core::fmt::{self, Write};
+
+use super::{BpfMapElemIterCtx, TaskIterCtx, TcpIterCtx};
+use crate::base_helper::termination_check;
+use crate::bindings::linux::kernel::bpf_iter_meta;
+use crate::bindings::uapi::linux::bpf::bpf_map_type;
+use crate::bindings::uapi::linux::errno::E2BIG;
+use crate::ffi;
+use crate::log::LogBuf;
+use crate::prog_type::rex_prog;
+use crate::task_struct::TaskStruct;
+use crate::utils::*;
+
+pub trait IterContext {
+    #[doc(hidden)]
+    fn meta(&self) -> *mut bpf_iter_meta;
+
+    /// Number of objects visited in this read of the iterator
+    #[inline(always)]
+    fn seq_num(&self) -> u64 {
+        unsafe { (*self.meta()).seq_num }
+    }
+
+    /// Identifies a single open of the iterator across all its reads
+    #[inline(always)]
+    fn session_id(&self) -> u64 {
+        unsafe { (*self.meta()).session_id }
+    }
+}
+
+impl IterContext for TaskIterCtx {
+    #[inline(always)]
+    fn meta(&self) -> *mut bpf_iter_meta {
+        self.meta
+    }
+}
+
+impl<K, V> IterContext for BpfMapElemIterCtx<K, V>
+where
+    K: Copy + NoRef,
+    V: Copy + NoRef,
+{
+    #[inline(always)]
+    fn meta(&self) -> *mut bpf_iter_meta {
+        self.meta
+    }
+}
+
+impl IterContext for TcpIterCtx {
+    #[inline(always)]
+    fn meta(&self) -> *mut bpf_iter_meta {
+        self.meta
+    }
+}
+
+/// prog_fn should have &Self as its first argument
+///
+/// The program is invoked once for each object and a last time with no
+/// object. Returning `Ok(0)` moves on to the next object, returning `Ok(1)`
+/// makes the read stop and the program to be invoked again on the same
+/// object on the next read. An `Err` is treated the same as `Ok(0)`.
+#[repr(C)]
+pub struct iter<C: IterContext + 'static> {
+    prog: fn(&Self, &mut C) -> Result,
+}
+
+impl<C: IterContext + 'static> iter<C> {
+    crate::base_helper::base_helper_defs!();
+
+    pub const unsafe fn new(f: fn(&iter<C>, &mut C) -> Result) -> iter<C> {
+        Self { prog: f }
+    }
+
+    fn convert_ctx(&self, ctx: *mut ()) -> &'static mut C {
+        unsafe { &mut *(ctx as *mut C) }
+    }
+
+    pub fn bpf_get_current_task(&self) -> Option<TaskStruct> {
+        TaskStruct::get_current_task()
+    }
+
+    /// Writes `data` to the output of the iterator
+    #[inline(always)]
+    pub fn bpf_seq_write(&self, ctx: &mut C, data: &[u8]) -> Result {
+        termination_check!(unsafe {
+            to_result!(ffi::bpf_seq_write(
+                (*ctx.meta()).seq,
+                data.as_ptr() as *const (),
+                data.len() as u32
+            ))
+        })
+    }
+
+    /// Formats `args` and writes the result to the output of the iterator,
+    /// see [`rex_seq_printf`](crate::rex_seq_printf)
+    pub fn bpf_seq_printf(
+        &self,
+        ctx: &mut C,
+        args: fmt::Arguments<'_>,
+    ) -> Result {
+        let mut buf = LogBuf::new();
+        write!(&mut buf, "{}", args).map_err(|_| -(E2BIG as i32))?;
+        self.bpf_seq_write(ctx, buf.as_bytes())
+    }
+}
+
+impl<C: IterContext + 'static> rex_prog for iter<C> {
+    fn prog_run(&self, ctx: *mut ()) -> u32 {
+        let newctx = self.convert_ctx(ctx);
+        match (self.prog)(self, newctx) {
+            Ok(ret) => ret as u32,
+            Err(_) => 0,
+        }
+    }
+}
+
+/// `printf`-style convenience macro for `iter::bpf_seq_printf`, e.g.
+/// `rex_seq_printf!(obj, ctx, "{} {}\n", pid, comm)`
+#[macro_export]
+macro_rules! rex_seq_printf {
+    ($obj:expr, $ctx:expr, $($arg:tt)*) => {
+        $obj.bpf_seq_printf($ctx, format_args!($($arg)*))
+    };
+}
@@ -0,0 +1,5 @@
+mod binding;
+mod iter_impl;
+
+pub use binding::*;
+pub use iter_impl::*;
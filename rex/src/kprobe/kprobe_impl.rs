@@ -1,3 +1,5 @@
+use crate::base_helper::termination_check;
+use crate::bindings::linux::kernel::pt_regs;
 use crate::bindings::uapi::linux::bpf::bpf_map_type;
 use crate::prog_type::rex_prog;
 use crate::pt_regs::PtRegs;
@@ -8,13 +10,28 @@ use crate::{ffi, Result};
 #[repr(C)]
 pub struct kprobe {
     prog: fn(&Self, &mut PtRegs) -> Result,
+    multi: bool,
 }
 
 impl kprobe {
     crate::base_helper::base_helper_defs!();
 
     pub const unsafe fn new(f: fn(&kprobe, &mut PtRegs) -> Result) -> kprobe {
-        Self { prog: f }
+        Self {
+            prog: f,
+            multi: false,
+        }
+    }
+
+    /// Constructs a kprobe attached through `kprobe.multi`, i.e. one program
+    /// attached to all functions matching a list of patterns
+    pub const unsafe fn new_multi(
+        f: fn(&kprobe, &mut PtRegs) -> Result,
+    ) -> kprobe {
+        Self {
+            prog: f,
+            multi: true,
+        }
     }
 
     // Now returns a mutable ref, but since every reg is private the user prog
@@ -38,6 +55,19 @@ impl kprobe {
     pub fn bpf_get_current_task(&self) -> Option<TaskStruct> {
         TaskStruct::get_current_task()
     }
+
+    /// Returns the address of the probed function, this is mostly useful
+    /// for multi-attached kprobes to tell which function fired
+    pub fn bpf_get_func_ip(&self, regs: &PtRegs) -> u64 {
+        let regs = &regs.regs as *const pt_regs;
+        if self.multi {
+            termination_check!(unsafe {
+                ffi::bpf_get_func_ip_kprobe_multi(regs)
+            })
+        } else {
+            termination_check!(unsafe { ffi::bpf_get_func_ip_kprobe(regs) })
+        }
+    }
 }
 
 impl rex_prog for kprobe {
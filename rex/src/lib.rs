@@ -8,15 +8,36 @@
 )]
 #![allow(non_camel_case_types, internal_features)]
 
+pub mod cgroup_skb;
+pub mod cgroup_sysctl;
+pub mod conntrack;
+pub mod csum;
+pub mod fib;
+pub mod file;
+pub mod flow_dissector;
+pub mod iter;
 pub mod kprobe;
+pub mod lsm;
 pub mod map;
+pub mod netfilter;
+pub mod packet;
 pub mod perf_event;
 pub mod prog_type;
 pub mod pt_regs;
 pub mod sched_cls;
+pub mod sk_lookup;
+pub mod sk_msg;
+pub mod sk_reuseport;
+pub mod sk_skb;
+pub mod sock_ops;
+pub mod socket;
+pub mod socket_filter;
 pub mod spinlock;
+pub mod struct_ops;
 pub mod task_struct;
+pub mod tcp_sock;
 pub mod tracepoint;
+pub mod usdt;
 pub mod utils;
 pub mod xdp;
 
@@ -54,10 +75,21 @@ macro_rules! define_prog_entry {
     };
 }
 
+define_prog_entry!(cgroup_skb);
+define_prog_entry!(cgroup_sysctl);
+define_prog_entry!(flow_dissector);
 define_prog_entry!(kprobe);
+define_prog_entry!(netfilter);
 define_prog_entry!(perf_event);
 define_prog_entry!(xdp);
 define_prog_entry!(sched_cls);
+define_prog_entry!(sk_lookup);
+define_prog_entry!(sk_msg);
+define_prog_entry!(sk_reuseport);
+define_prog_entry!(sk_skb);
+define_prog_entry!(sock_ops);
+define_prog_entry!(socket_filter);
+define_prog_entry!(usdt);
 
 pub use bindings::uapi::*;
 pub use log::rex_trace_printk;
@@ -27,6 +27,12 @@ impl LogBuf {
     pub(crate) fn reset(&mut self) {
         self.off = 0;
     }
+
+    /// Contents written to the buffer so far, without the null terminator
+    #[inline(always)]
+    pub(crate) fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.off]
+    }
 }
 
 impl Write for LogBuf {
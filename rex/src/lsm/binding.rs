@@ -0,0 +1,99 @@
+use core::{mem, slice};
+
+use crate::bindings::linux::kernel::{file, task_struct};
+use crate::bindings::uapi::linux::r#in::__kernel_sockaddr_storage;
+use crate::file::File;
+use crate::task_struct::TaskStruct;
+
+// The context of an LSM program is an array of u64 holding the arguments of
+// the hook, followed by the return value of the previous LSM program
+
+/// `int file_open(struct file *file)`
+#[repr(C)]
+pub struct FileOpenCtx {
+    file: *const file,
+    ret: i64,
+}
+
+impl FileOpenCtx {
+    #[inline(always)]
+    pub fn file(&self) -> File {
+        File::new(unsafe { &*self.file })
+    }
+
+    /// Return value of the previous LSM program on this hook
+    #[inline(always)]
+    pub fn prev_ret(&self) -> i32 {
+        self.ret as i32
+    }
+}
+
+/// `int task_kill(struct task_struct *p, struct kernel_siginfo *info, int
+/// sig, const struct cred *cred)`
+#[repr(C)]
+pub struct TaskKillCtx {
+    p: *const task_struct,
+    info: u64,
+    sig: i64,
+    cred: u64,
+    ret: i64,
+}
+
+impl TaskKillCtx {
+    /// The task receiving the signal
+    #[inline(always)]
+    pub fn target(&self) -> TaskStruct {
+        TaskStruct::new(unsafe { &*self.p })
+    }
+
+    #[inline(always)]
+    pub fn sig(&self) -> i32 {
+        self.sig as i32
+    }
+
+    /// Return value of the previous LSM program on this hook
+    #[inline(always)]
+    pub fn prev_ret(&self) -> i32 {
+        self.ret as i32
+    }
+}
+
+/// `int socket_connect(struct socket *sock, struct sockaddr *address, int
+/// addrlen)`
+#[repr(C)]
+pub struct SocketConnectCtx {
+    sock: u64,
+    address: *const u8,
+    addrlen: i64,
+    ret: i64,
+}
+
+impl SocketConnectCtx {
+    /// Raw bytes of the `struct sockaddr` being connected to, the address
+    /// has already been copied into the kernel
+    #[inline(always)]
+    pub fn address(&self) -> &[u8] {
+        // addrlen is an int, only the lower half of the slot is defined.
+        // In-kernel callers (e.g. kernel_connect) are not bounded by
+        // move_addr_to_kernel, so reject negative lengths and clamp to a
+        // sockaddr_storage.
+        let len = match usize::try_from(self.addrlen as i32) {
+            Ok(len) => len.min(mem::size_of::<__kernel_sockaddr_storage>()),
+            Err(_) => 0,
+        };
+        unsafe { slice::from_raw_parts(self.address, len) }
+    }
+
+    /// Address family, i.e. `sa_family` of the address
+    #[inline(always)]
+    pub fn family(&self) -> Option<u16> {
+        let addr = self.address();
+        Some(u16::from_ne_bytes([*addr.first()?, *addr.get(1)?]))
+    }
+
+    /// Return value of the previous LSM program on this hook
+    #[inline(always)]
+    pub fn prev_ret(&self) -> i32 {
+        self.ret as i32
+    }
+}
@@ -0,0 +1,46 @@
+use super::{FileOpenCtx, SocketConnectCtx, TaskKillCtx};
+use crate::bindings::uapi::linux::bpf::bpf_map_type;
+use crate::linux::errno::EPERM;
+use crate::prog_type::rex_prog;
+use crate::task_struct::TaskStruct;
+use crate::Result;
+
+pub trait LsmContext {}
+impl LsmContext for FileOpenCtx {}
+impl LsmContext for TaskKillCtx {}
+impl LsmContext for SocketConnectCtx {}
+
+/// prog_fn should have &Self as its first argument
+///
+/// Returning `Ok` allows the operation, returning `Err` denies it with
+/// `-EPERM`.
+#[repr(C)]
+pub struct lsm<C: LsmContext + 'static> {
+    prog: fn(&Self, &'static C) -> Result,
+}
+
+impl<C: LsmContext + 'static> lsm<C> {
+    crate::base_helper::base_helper_defs!();
+
+    pub const unsafe fn new(f: fn(&lsm<C>, &'static C) -> Result) -> lsm<C> {
+        Self { prog: f }
+    }
+
+    fn convert_ctx(&self, ctx: *mut ()) -> &'static C {
+        unsafe { &*(ctx as *mut C) }
+    }
+
+    pub fn bpf_get_current_task(&self) -> Option<TaskStruct> {
+        TaskStruct::get_current_task()
+    }
+}
+
+impl<C: LsmContext + 'static> rex_prog for lsm<C> {
+    fn prog_run(&self, ctx: *mut ()) -> u32 {
+        let newctx = self.convert_ctx(ctx);
+        match (self.prog)(self, newctx) {
+            Ok(_) => 0,
+            Err(_) => -(EPERM as i32) as u32,
+        }
+    }
+}
@@ -0,0 +1,5 @@
+mod binding;
+mod lsm_impl;
+
+pub use binding::*;
+pub use lsm_impl::*;
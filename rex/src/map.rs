@@ -12,7 +12,8 @@ use crate::ffi;
 use crate::linux::bpf::{
     bpf_map_type, BPF_ANY, BPF_EXIST, BPF_MAP_TYPE_ARRAY, BPF_MAP_TYPE_HASH,
     BPF_MAP_TYPE_PERCPU_ARRAY, BPF_MAP_TYPE_PERF_EVENT_ARRAY,
-    BPF_MAP_TYPE_QUEUE, BPF_MAP_TYPE_RINGBUF, BPF_MAP_TYPE_STACK,
+    BPF_MAP_TYPE_QUEUE, BPF_MAP_TYPE_REUSEPORT_SOCKARRAY, BPF_MAP_TYPE_RINGBUF,
+    BPF_MAP_TYPE_SOCKHASH, BPF_MAP_TYPE_SOCKMAP, BPF_MAP_TYPE_STACK,
     BPF_MAP_TYPE_STACK_TRACE, BPF_NOEXIST, BPF_RB_AVAIL_DATA, BPF_RB_CONS_POS,
     BPF_RB_PROD_POS, BPF_RB_RING_SIZE,
 };
@@ -76,6 +77,23 @@ pub type RexHashMap<K, V> = RexMapHandle<BPF_MAP_TYPE_HASH, K, V>;
 pub type RexStack<V> = RexMapHandle<BPF_MAP_TYPE_STACK, (), V>;
 pub type RexQueue<V> = RexMapHandle<BPF_MAP_TYPE_QUEUE, (), V>;
 pub type RexRingBuf = RexMapHandle<BPF_MAP_TYPE_RINGBUF, (), ()>;
+/// Value is the socket fd when updated from userspace
+pub type RexSockMap = RexMapHandle<BPF_MAP_TYPE_SOCKMAP, u32, u32>;
+pub type RexSockHash<K> = RexMapHandle<BPF_MAP_TYPE_SOCKHASH, K, u32>;
+pub type RexReuseportSockArray =
+    RexMapHandle<BPF_MAP_TYPE_REUSEPORT_SOCKARRAY, u32, u32>;
+
+/// Map types whose elements can be looked up in place by the programs.
+///
+/// Socket maps are excluded since a lookup on them returns a referenced
+/// `struct sock` rather than the stored value, other maps (e.g. the stack
+/// trace and perf event array maps) do not support lookups from the program
+/// side at all.
+pub trait RexLookupMap {}
+
+impl<K, V> RexLookupMap for RexHashMap<K, V> where V: Copy + NoRef {}
+impl<V> RexLookupMap for RexArrayMap<V> where V: Copy + NoRef {}
+impl<V> RexLookupMap for RexPerCPUArrayMap<V> where V: Copy + NoRef {}
 
 impl<'a, K, V> RexHashMap<K, V>
 where
@@ -119,6 +137,26 @@ where
     }
 }
 
+// Sockets are inserted from userspace, the program side only redirects to
+// them (see the `sk_msg` and `sk_skb` program types) or removes them
+impl RexSockMap {
+    pub fn delete(&'static self, key: &u32) -> Result {
+        bpf_map_delete_elem(self, key)
+    }
+}
+
+impl<K> RexSockHash<K> {
+    pub fn delete(&'static self, key: &K) -> Result {
+        bpf_map_delete_elem(self, key)
+    }
+}
+
+impl RexReuseportSockArray {
+    pub fn delete(&'static self, key: &u32) -> Result {
+        bpf_map_delete_elem(self, key)
+    }
+}
+
 impl<V> RexPerfEventArray<V>
 where
     V: Copy + NoRef,
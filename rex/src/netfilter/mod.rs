@@ -0,0 +1,3 @@
+mod netfilter_impl;
+
+pub use netfilter_impl::*;
@@ -0,0 +1,154 @@
+use core::ffi::{c_int, c_uchar};
+use core::slice;
+
+use crate::bindings::linux::kernel::{
+    bpf_nf_ctx as bpf_nf_ctx_kern, net_device, nf_hook_state, sk_buff, sock,
+};
+use crate::bindings::uapi::linux::bpf::bpf_map_type;
+// expose the following constants to the user
+pub use crate::bindings::uapi::linux::netfilter::{NFPROTO_IPV4, NFPROTO_IPV6};
+use crate::bindings::uapi::linux::netfilter::{
+    NF_ACCEPT, NF_DROP, NF_INET_FORWARD, NF_INET_LOCAL_IN, NF_INET_LOCAL_OUT,
+    NF_INET_POST_ROUTING, NF_INET_PRE_ROUTING,
+};
+use crate::prog_type::rex_prog;
+use crate::sched_cls::skb_net_header_defs;
+use crate::utils::*;
+
+/// Verdict of a netfilter program on the packet
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NfVerdict {
+    Drop = NF_DROP,
+    Accept = NF_ACCEPT,
+}
+
+/// Return type of a netfilter program, an `Err` drops the packet
+pub type NfResult = core::result::Result<NfVerdict, c_int>;
+
+/// Netfilter hooks of the IPv4 and IPv6 families
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NfInetHook {
+    PreRouting = NF_INET_PRE_ROUTING,
+    LocalIn = NF_INET_LOCAL_IN,
+    Forward = NF_INET_FORWARD,
+    LocalOut = NF_INET_LOCAL_OUT,
+    PostRouting = NF_INET_POST_ROUTING,
+}
+
+pub struct bpf_nf_ctx<'a> {
+    /// Packet data starting at the network header, the packet cannot be
+    /// modified by the program
+    pub data_slice: &'a [c_uchar],
+    state: &'static nf_hook_state,
+    skb: &'static sk_buff,
+}
+
+// Define accessors of program-accessible fields
+impl bpf_nf_ctx<'_> {
+    /// The hook the program is invoked on, `None` if it is not one of
+    /// `enum nf_inet_hooks`
+    #[inline(always)]
+    pub fn hook(&self) -> Option<NfInetHook> {
+        match self.state.hook as u32 {
+            NF_INET_PRE_ROUTING => Some(NfInetHook::PreRouting),
+            NF_INET_LOCAL_IN => Some(NfInetHook::LocalIn),
+            NF_INET_FORWARD => Some(NfInetHook::Forward),
+            NF_INET_LOCAL_OUT => Some(NfInetHook::LocalOut),
+            NF_INET_POST_ROUTING => Some(NfInetHook::PostRouting),
+            _ => None,
+        }
+    }
+
+    /// Protocol family of the hook, i.e. `NFPROTO_IPV4` or `NFPROTO_IPV6`
+    #[inline(always)]
+    pub fn pf(&self) -> u8 {
+        self.state.pf
+    }
+
+    /// Index of the input device, not available on the output hooks
+    #[inline(always)]
+    pub fn in_ifindex(&self) -> Option<u32> {
+        Self::ifindex(self.state.in_)
+    }
+
+    /// Index of the output device, not available on the input hooks
+    #[inline(always)]
+    pub fn out_ifindex(&self) -> Option<u32> {
+        Self::ifindex(self.state.out)
+    }
+
+    #[inline(always)]
+    fn ifindex(dev: *mut net_device) -> Option<u32> {
+        unsafe { dev.as_ref() }.map(|dev| dev.ifindex as u32)
+    }
+
+    /// The local socket of the packet, if any
+    #[inline(always)]
+    pub fn sk(&self) -> Option<&sock> {
+        unsafe { self.state.sk.as_ref() }
+    }
+
+    #[inline(always)]
+    pub fn len(&self) -> u32 {
+        self.skb.len
+    }
+
+    #[inline(always)]
+    pub fn protocol(&self) -> u16be {
+        u16be(unsafe {
+            (self.skb.__bindgen_anon_4.__bindgen_anon_1)
+                .as_ref()
+                .protocol
+        })
+    }
+}
+
+/// prog_fn should have &Self as its first argument
+#[repr(C)]
+pub struct netfilter {
+    prog: fn(&Self, &mut bpf_nf_ctx) -> NfResult,
+}
+
+impl netfilter {
+    crate::base_helper::base_helper_defs!();
+
+    // The headers are located with the header offsets of the skb, the
+    // accessors return `None` if the header is not set or is not in the
+    // linear data
+    skb_net_header_defs!(bpf_nf_ctx, |ctx: &bpf_nf_ctx| ctx.skb);
+
+    pub const unsafe fn new(
+        f: fn(&netfilter, &mut bpf_nf_ctx) -> NfResult,
+    ) -> netfilter {
+        Self { prog: f }
+    }
+
+    #[inline(always)]
+    fn convert_ctx(&self, ctx: *mut ()) -> bpf_nf_ctx {
+        let kptr = unsafe { &*(ctx as *mut bpf_nf_ctx_kern) };
+        let state = unsafe { &*kptr.state };
+        let skb = unsafe { &*kptr.skb };
+
+        // NOTE: not support non-linear sk_buff yet
+        let data_length = (skb.len - skb.data_len) as usize;
+        let data_slice = unsafe {
+            slice::from_raw_parts(skb.data as *const c_uchar, data_length)
+        };
+
+        bpf_nf_ctx {
+            data_slice,
+            state,
+            skb,
+        }
+    }
+}
+
+impl rex_prog for netfilter {
+    fn prog_run(&self, ctx: *mut ()) -> u32 {
+        let mut newctx = self.convert_ctx(ctx);
+        // drop the packet if error
+        ((self.prog)(self, &mut newctx)).unwrap_or(NfVerdict::Drop) as u32
+    }
+}
@@ -0,0 +1,884 @@
+use core::ffi::c_uchar;
+use core::mem::size_of;
+
+pub use crate::bindings::linux::kernel::{
+    arphdr, ethhdr, frag_hdr, icmp6hdr, icmphdr, iphdr, ipv6_opt_hdr,
+    ipv6_rt_hdr, ipv6hdr, tcphdr, udphdr, vlan_hdr,
+};
+// expose the following constants to the user
+pub use crate::bindings::uapi::linux::icmp::{
+    ICMP_DEST_UNREACH, ICMP_ECHO, ICMP_ECHOREPLY, ICMP_TIME_EXCEEDED,
+};
+pub use crate::bindings::uapi::linux::icmpv6::{
+    ICMPV6_DEST_UNREACH, ICMPV6_ECHO_REPLY, ICMPV6_ECHO_REQUEST,
+    ICMPV6_PKT_TOOBIG, ICMPV6_TIME_EXCEED,
+};
+pub use crate::bindings::uapi::linux::if_arp::{ARPOP_REPLY, ARPOP_REQUEST};
+pub use crate::bindings::uapi::linux::if_ether::{
+    ETH_P_8021AD, ETH_P_8021Q, ETH_P_ARP, ETH_P_IP, ETH_P_IPV6,
+};
+pub use crate::bindings::uapi::linux::in6::{
+    IPPROTO_DSTOPTS, IPPROTO_FRAGMENT, IPPROTO_HOPOPTS, IPPROTO_ICMPV6,
+    IPPROTO_ROUTING,
+};
+pub use crate::bindings::uapi::linux::r#in::{
+    IPPROTO_ICMP, IPPROTO_TCP, IPPROTO_UDP,
+};
+use crate::csum;
+use crate::utils::{
+    convert_slice_to_struct_mut, u16be, u32be, AlignedMut, NoRef,
+};
+
+/// Maximum number of IPv6 extension headers skipped by
+/// [`PacketCursor::ipv6_ext`]
+pub(crate) const MAX_IPV6_EXT_HDRS: usize = 8;
+
+/// Octets in an Ethernet address
+const ETH_ALEN: usize = 6;
+
+/// Views a `__be16` header field as a [`u16be`]
+#[inline(always)]
+fn as_u16be(field: &mut u16) -> &mut u16be {
+    // u16be is repr(transparent)
+    unsafe { &mut *(field as *mut u16 as *mut u16be) }
+}
+
+/// Views a `__be32` header field as a [`u32be`]
+#[inline(always)]
+fn as_u32be(field: &mut u32) -> &mut u32be {
+    // u32be is repr(transparent)
+    unsafe { &mut *(field as *mut u32 as *mut u32be) }
+}
+
+impl ethhdr {
+    /// EtherType of the frame, i.e. `h_proto`
+    #[inline(always)]
+    pub fn proto(&mut self) -> &mut u16be {
+        as_u16be(&mut self.h_proto)
+    }
+}
+
+impl iphdr {
+    #[inline(always)]
+    pub fn saddr(&mut self) -> &mut u32be {
+        as_u32be(unsafe { &mut self.__bindgen_anon_1.__bindgen_anon_1.saddr })
+    }
+
+    #[inline(always)]
+    pub fn daddr(&mut self) -> &mut u32be {
+        as_u32be(unsafe { &mut self.__bindgen_anon_1.__bindgen_anon_1.daddr })
+    }
+
+    #[inline(always)]
+    pub fn tot_len(&mut self) -> &mut u16be {
+        as_u16be(&mut self.tot_len)
+    }
+}
+
+impl ipv6hdr {
+    #[inline(always)]
+    pub fn payload_len(&mut self) -> &mut u16be {
+        as_u16be(&mut self.payload_len)
+    }
+
+    #[inline(always)]
+    pub fn saddr(&mut self) -> &mut [u8; 16] {
+        unsafe {
+            &mut self.__bindgen_anon_1.__bindgen_anon_1.saddr.in6_u.u6_addr8
+        }
+    }
+
+    #[inline(always)]
+    pub fn daddr(&mut self) -> &mut [u8; 16] {
+        unsafe {
+            &mut self.__bindgen_anon_1.__bindgen_anon_1.daddr.in6_u.u6_addr8
+        }
+    }
+}
+
+impl tcphdr {
+    #[inline(always)]
+    pub fn source(&mut self) -> &mut u16be {
+        as_u16be(&mut self.source)
+    }
+
+    #[inline(always)]
+    pub fn dest(&mut self) -> &mut u16be {
+        as_u16be(&mut self.dest)
+    }
+
+    #[inline(always)]
+    pub fn seq(&mut self) -> &mut u32be {
+        as_u32be(&mut self.seq)
+    }
+
+    #[inline(always)]
+    pub fn ack_seq(&mut self) -> &mut u32be {
+        as_u32be(&mut self.ack_seq)
+    }
+}
+
+impl udphdr {
+    #[inline(always)]
+    pub fn source(&mut self) -> &mut u16be {
+        as_u16be(&mut self.source)
+    }
+
+    #[inline(always)]
+    pub fn dest(&mut self) -> &mut u16be {
+        as_u16be(&mut self.dest)
+    }
+
+    #[inline(always)]
+    pub fn len(&mut self) -> &mut u16be {
+        as_u16be(&mut self.len)
+    }
+}
+
+impl icmphdr {
+    /// Identifier of an echo request or reply
+    #[inline(always)]
+    pub fn echo_id(&mut self) -> &mut u16be {
+        as_u16be(unsafe { &mut self.un.echo.id })
+    }
+
+    /// Sequence number of an echo request or reply
+    #[inline(always)]
+    pub fn echo_sequence(&mut self) -> &mut u16be {
+        as_u16be(unsafe { &mut self.un.echo.sequence })
+    }
+
+    /// Gateway address of a redirect message
+    #[inline(always)]
+    pub fn gateway(&mut self) -> &mut u32be {
+        as_u32be(unsafe { &mut self.un.gateway })
+    }
+
+    /// Next-hop MTU of a "fragmentation needed" message
+    #[inline(always)]
+    pub fn frag_mtu(&mut self) -> &mut u16be {
+        as_u16be(unsafe { &mut self.un.frag.mtu })
+    }
+}
+
+impl icmp6hdr {
+    /// Identifier of an echo request or reply
+    #[inline(always)]
+    pub fn echo_id(&mut self) -> &mut u16be {
+        as_u16be(unsafe { &mut self.icmp6_dataun.u_echo.identifier })
+    }
+
+    /// Sequence number of an echo request or reply
+    #[inline(always)]
+    pub fn echo_sequence(&mut self) -> &mut u16be {
+        as_u16be(unsafe { &mut self.icmp6_dataun.u_echo.sequence })
+    }
+
+    /// MTU of a "packet too big" message
+    #[inline(always)]
+    pub fn mtu(&mut self) -> &mut u32be {
+        as_u32be(unsafe { &mut self.icmp6_dataun.un_data32[0] })
+    }
+}
+
+impl arphdr {
+    /// ARP opcode, e.g. `ARPOP_REQUEST`
+    #[inline(always)]
+    pub fn op(&mut self) -> &mut u16be {
+        as_u16be(&mut self.ar_op)
+    }
+}
+
+impl vlan_hdr {
+    /// EtherType of the encapsulated frame
+    #[inline(always)]
+    pub fn encapsulated_proto(&mut self) -> &mut u16be {
+        as_u16be(&mut self.h_vlan_encapsulated_proto)
+    }
+
+    /// VLAN identifier, i.e. the low 12 bits of the TCI
+    #[inline(always)]
+    pub fn vid(&self) -> u16 {
+        u16::from_be(self.h_vlan_TCI) & 0x0fff
+    }
+
+    /// Priority code point, i.e. the high 3 bits of the TCI
+    #[inline(always)]
+    pub fn pcp(&self) -> u8 {
+        (u16::from_be(self.h_vlan_TCI) >> 13) as u8
+    }
+}
+
+impl ipv6_opt_hdr {
+    /// Length of the header in bytes, including the options
+    #[inline(always)]
+    pub fn hdr_len(&self) -> usize {
+        (self.hdrlen as usize + 1) * 8
+    }
+}
+
+impl ipv6_rt_hdr {
+    /// Length of the header in bytes, including the type-specific data
+    #[inline(always)]
+    pub fn hdr_len(&self) -> usize {
+        (self.hdrlen as usize + 1) * 8
+    }
+}
+
+impl frag_hdr {
+    /// Offset of the fragment in bytes
+    #[inline(always)]
+    pub fn offset(&self) -> u16 {
+        u16::from_be(self.frag_off) & 0xfff8
+    }
+
+    /// Whether this is not the last fragment
+    #[inline(always)]
+    pub fn more_fragments(&self) -> bool {
+        u16::from_be(self.frag_off) & 0x1 != 0
+    }
+}
+
+/// Errors of [`PacketCursor`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PacketError {
+    /// The packet is too short for the header
+    Truncated,
+    /// The next header is not of the requested protocol
+    UnexpectedProtocol,
+    /// A length field of the header is invalid, e.g. an IPv4 IHL below 5
+    Malformed,
+    /// The header has not been parsed by the cursor
+    NotParsed,
+}
+
+/// Protocol of the next header to be parsed
+#[derive(Debug, Copy, Clone)]
+enum NextProto {
+    /// Nothing parsed yet, expecting an Ethernet header
+    Eth,
+    /// `ETH_P_*` in host byte order
+    EtherType(u16),
+    /// `IPPROTO_*`
+    IpProto(u8),
+    /// Past the transport header
+    Payload,
+}
+
+/// A bounds-checked parser over the packet data, e.g.
+///
+/// ```
+/// let mut cursor = PacketCursor::new(&mut ctx.data_slice)
+///     .eth()?
+///     .vlan()?
+///     .ipv4()?
+///     .udp()?;
+/// let dest = *cursor.udp_header()?.dest();
+/// let payload = cursor.payload();
+/// ```
+///
+/// Each step locates the header at its real offset, taking VLAN tags, IPv4
+/// options, IPv6 extension headers and the TCP data offset into account, and
+/// fails with a [`PacketError`] instead of panicking on short or unexpected
+/// packets.
+pub struct PacketCursor<'a> {
+    data: &'a mut [c_uchar],
+    off: usize,
+    next: NextProto,
+    eth_off: Option<usize>,
+    vlan_off: Option<usize>,
+    /// Offset and `ETH_P_*` of the network header
+    l3: Option<(usize, u16)>,
+    frag_off: Option<usize>,
+    /// Offset and `IPPROTO_*` of the transport header
+    l4: Option<(usize, u8)>,
+}
+
+impl<'a> PacketCursor<'a> {
+    /// Creates a cursor at the start of `data`, which is expected to start
+    /// with an Ethernet header (e.g. `xdp_md::data_slice` or
+    /// `__sk_buff::data_slice` of TC)
+    #[inline(always)]
+    pub fn new(data: &'a mut [c_uchar]) -> Self {
+        Self {
+            data,
+            off: 0,
+            next: NextProto::Eth,
+            eth_off: None,
+            vlan_off: None,
+            l3: None,
+            frag_off: None,
+            l4: None,
+        }
+    }
+
+    /// Offset of the next header to be parsed, i.e. the payload offset after
+    /// the last header
+    #[inline(always)]
+    pub fn offset(&self) -> usize {
+        self.off
+    }
+
+    /// Checks that `len` bytes are available at the current offset
+    #[inline(always)]
+    fn ensure(&self, len: usize) -> Result<(), PacketError> {
+        if self.data.len() < self.off + len {
+            Err(PacketError::Truncated)
+        } else {
+            Ok(())
+        }
+    }
+
+    #[inline(always)]
+    fn read_u8(&self, off: usize) -> u8 {
+        self.data[off]
+    }
+
+    #[inline(always)]
+    fn read_be16(&self, off: usize) -> u16 {
+        u16::from_be_bytes([self.data[off], self.data[off + 1]])
+    }
+
+    #[inline(always)]
+    fn write_u8(&mut self, off: usize, val: u8) {
+        self.data[off] = val;
+    }
+
+    #[inline(always)]
+    fn write_be16(&mut self, off: usize, val: u16) {
+        self.data[off..off + 2].copy_from_slice(&val.to_be_bytes());
+    }
+
+    /// Stores a checksum as computed by [`crate::csum`], i.e. in packet
+    /// byte order
+    #[inline(always)]
+    fn write_csum(&mut self, off: usize, check: u16) {
+        self.data[off..off + 2].copy_from_slice(&check.to_ne_bytes());
+    }
+
+    #[inline(always)]
+    fn swap_bytes(&mut self, a: usize, b: usize, len: usize) {
+        for i in 0..len {
+            self.data.swap(a + i, b + i);
+        }
+    }
+
+    #[inline(always)]
+    fn expect_ether_type(&self, proto: u32) -> Result<(), PacketError> {
+        match self.next {
+            NextProto::EtherType(p) if p as u32 == proto => Ok(()),
+            _ => Err(PacketError::UnexpectedProtocol),
+        }
+    }
+
+    #[inline(always)]
+    fn expect_ip_proto(&self, proto: u32) -> Result<(), PacketError> {
+        match self.next {
+            NextProto::IpProto(p) if p as u32 == proto => Ok(()),
+            _ => Err(PacketError::UnexpectedProtocol),
+        }
+    }
+
+    /// Parses the Ethernet header
+    #[inline(always)]
+    pub fn eth(mut self) -> Result<Self, PacketError> {
+        if !matches!(self.next, NextProto::Eth) {
+            return Err(PacketError::UnexpectedProtocol);
+        }
+        self.ensure(size_of::<ethhdr>())?;
+
+        let proto =
+            self.read_be16(self.off + core::mem::offset_of!(ethhdr, h_proto));
+        self.eth_off = Some(self.off);
+        self.off += size_of::<ethhdr>();
+        self.next = NextProto::EtherType(proto);
+        Ok(self)
+    }
+
+    /// Parses an 802.1Q or 802.1ad VLAN tag if there is one, otherwise the
+    /// cursor is returned unchanged. Call it once per expected tag.
+    #[inline(always)]
+    pub fn vlan(mut self) -> Result<Self, PacketError> {
+        let NextProto::EtherType(proto) = self.next else {
+            return Err(PacketError::UnexpectedProtocol);
+        };
+        if proto as u32 != ETH_P_8021Q && proto as u32 != ETH_P_8021AD {
+            return Ok(self);
+        }
+        self.ensure(size_of::<vlan_hdr>())?;
+
+        let proto = self.read_be16(
+            self.off +
+                core::mem::offset_of!(vlan_hdr, h_vlan_encapsulated_proto),
+        );
+        // Keep the outer tag of stacked (QinQ) tags
+        self.vlan_off.get_or_insert(self.off);
+        self.off += size_of::<vlan_hdr>();
+        self.next = NextProto::EtherType(proto);
+        Ok(self)
+    }
+
+    /// Parses the fixed part of an ARP header, the addresses follow it in
+    /// the payload
+    #[inline(always)]
+    pub fn arp(mut self) -> Result<Self, PacketError> {
+        self.expect_ether_type(ETH_P_ARP)?;
+        self.ensure(size_of::<arphdr>())?;
+
+        self.l3 = Some((self.off, ETH_P_ARP as u16));
+        self.off += size_of::<arphdr>();
+        self.next = NextProto::Payload;
+        Ok(self)
+    }
+
+    /// Parses the IPv4 header, including its options
+    #[inline(always)]
+    pub fn ipv4(mut self) -> Result<Self, PacketError> {
+        self.expect_ether_type(ETH_P_IP)?;
+        self.ensure(size_of::<iphdr>())?;
+
+        // The first byte holds the version and the IHL in 32-bit words
+        let ihl = (self.read_u8(self.off) & 0xf) as usize * 4;
+        if ihl < size_of::<iphdr>() {
+            return Err(PacketError::Malformed);
+        }
+        self.ensure(ihl)?;
+
+        let proto =
+            self.read_u8(self.off + core::mem::offset_of!(iphdr, protocol));
+        self.l3 = Some((self.off, ETH_P_IP as u16));
+        self.off += ihl;
+        self.next = NextProto::IpProto(proto);
+        Ok(self)
+    }
+
+    /// Parses the fixed IPv6 header, use [`Self::ipv6_ext`] afterwards to
+    /// skip the extension headers
+    #[inline(always)]
+    pub fn ipv6(mut self) -> Result<Self, PacketError> {
+        self.expect_ether_type(ETH_P_IPV6)?;
+        self.ensure(size_of::<ipv6hdr>())?;
+
+        let proto =
+            self.read_u8(self.off + core::mem::offset_of!(ipv6hdr, nexthdr));
+        self.l3 = Some((self.off, ETH_P_IPV6 as u16));
+        self.off += size_of::<ipv6hdr>();
+        self.next = NextProto::IpProto(proto);
+        Ok(self)
+    }
+
+    /// Skips the hop-by-hop, routing, fragment and destination options
+    /// headers following the IPv6 header, if there are any
+    #[inline(always)]
+    pub fn ipv6_ext(mut self) -> Result<Self, PacketError> {
+        if !matches!(self.l3, Some((_, p)) if p as u32 == ETH_P_IPV6) {
+            return Err(PacketError::UnexpectedProtocol);
+        }
+
+        for _ in 0..MAX_IPV6_EXT_HDRS {
+            let NextProto::IpProto(proto) = self.next else {
+                return Err(PacketError::UnexpectedProtocol);
+            };
+
+            let len = match proto as u32 {
+                IPPROTO_HOPOPTS | IPPROTO_ROUTING | IPPROTO_DSTOPTS => {
+                    self.ensure(size_of::<ipv6_opt_hdr>())?;
+                    let hdrlen = self.read_u8(
+                        self.off + core::mem::offset_of!(ipv6_opt_hdr, hdrlen),
+                    );
+                    (hdrlen as usize + 1) * 8
+                }
+                IPPROTO_FRAGMENT => {
+                    self.frag_off = Some(self.off);
+                    size_of::<frag_hdr>()
+                }
+                _ => return Ok(self),
+            };
+            self.ensure(len)?;
+
+            // All extension headers start with the next header field
+            self.next = NextProto::IpProto(self.read_u8(self.off));
+            self.off += len;
+        }
+
+        Err(PacketError::Malformed)
+    }
+
+    /// Parses either an IPv4 or an IPv6 header, depending on the EtherType.
+    /// IPv6 extension headers are skipped.
+    #[inline(always)]
+    pub fn ip(self) -> Result<Self, PacketError> {
+        match self.next {
+            NextProto::EtherType(p) if p as u32 == ETH_P_IPV6 => {
+                self.ipv6()?.ipv6_ext()
+            }
+            _ => self.ipv4(),
+        }
+    }
+
+    /// Parses the TCP, UDP, ICMP or ICMPv6 header following the IP header,
+    /// other transport protocols are left unparsed
+    #[inline(always)]
+    pub fn l4(self) -> Result<Self, PacketError> {
+        let NextProto::IpProto(proto) = self.next else {
+            return Err(PacketError::UnexpectedProtocol);
+        };
+
+        match proto as u32 {
+            IPPROTO_TCP => self.tcp(),
+            IPPROTO_UDP => self.udp(),
+            IPPROTO_ICMP => self.icmp(),
+            IPPROTO_ICMPV6 => self.icmp6(),
+            _ => Ok(self),
+        }
+    }
+
+    /// Parses the TCP header, including its options
+    #[inline(always)]
+    pub fn tcp(mut self) -> Result<Self, PacketError> {
+        self.expect_ip_proto(IPPROTO_TCP)?;
+        self.ensure(size_of::<tcphdr>())?;
+
+        // The high 4 bits of byte 12 hold the data offset in 32-bit words
+        let doff = (self.read_u8(self.off + 12) >> 4) as usize * 4;
+        if doff < size_of::<tcphdr>() {
+            return Err(PacketError::Malformed);
+        }
+        self.ensure(doff)?;
+
+        self.l4 = Some((self.off, IPPROTO_TCP as u8));
+        self.off += doff;
+        self.next = NextProto::Payload;
+        Ok(self)
+    }
+
+    /// Parses the UDP header
+    #[inline(always)]
+    pub fn udp(mut self) -> Result<Self, PacketError> {
+        self.expect_ip_proto(IPPROTO_UDP)?;
+        self.ensure(size_of::<udphdr>())?;
+
+        self.l4 = Some((self.off, IPPROTO_UDP as u8));
+        self.off += size_of::<udphdr>();
+        self.next = NextProto::Payload;
+        Ok(self)
+    }
+
+    /// Parses the ICMP header
+    #[inline(always)]
+    pub fn icmp(mut self) -> Result<Self, PacketError> {
+        self.expect_ip_proto(IPPROTO_ICMP)?;
+        self.ensure(size_of::<icmphdr>())?;
+
+        self.l4 = Some((self.off, IPPROTO_ICMP as u8));
+        self.off += size_of::<icmphdr>();
+        self.next = NextProto::Payload;
+        Ok(self)
+    }
+
+    /// Parses the ICMPv6 header
+    #[inline(always)]
+    pub fn icmp6(mut self) -> Result<Self, PacketError> {
+        self.expect_ip_proto(IPPROTO_ICMPV6)?;
+        self.ensure(size_of::<icmp6hdr>())?;
+
+        self.l4 = Some((self.off, IPPROTO_ICMPV6 as u8));
+        self.off += size_of::<icmp6hdr>();
+        self.next = NextProto::Payload;
+        Ok(self)
+    }
+
+    #[inline(always)]
+    fn header<T: Copy + NoRef>(
+        &mut self,
+        off: Option<usize>,
+    ) -> Result<AlignedMut<'_, T>, PacketError> {
+        let off = off.ok_or(PacketError::NotParsed)?;
+        Ok(convert_slice_to_struct_mut::<T>(
+            &mut self.data[off..off + size_of::<T>()],
+        ))
+    }
+
+    #[inline(always)]
+    fn l3_header<T: Copy + NoRef>(
+        &mut self,
+        proto: u32,
+    ) -> Result<AlignedMut<'_, T>, PacketError> {
+        match self.l3 {
+            Some((off, p)) if p as u32 == proto => self.header(Some(off)),
+            Some(_) => Err(PacketError::UnexpectedProtocol),
+            None => Err(PacketError::NotParsed),
+        }
+    }
+
+    #[inline(always)]
+    fn l4_header<T: Copy + NoRef>(
+        &mut self,
+        proto: u32,
+    ) -> Result<AlignedMut<'_, T>, PacketError> {
+        match self.l4 {
+            Some((off, p)) if p as u32 == proto => self.header(Some(off)),
+            Some(_) => Err(PacketError::UnexpectedProtocol),
+            None => Err(PacketError::NotParsed),
+        }
+    }
+
+    #[inline(always)]
+    pub fn eth_header(
+        &mut self,
+    ) -> Result<AlignedMut<'_, ethhdr>, PacketError> {
+        self.header(self.eth_off)
+    }
+
+    /// The outermost VLAN tag parsed by [`Self::vlan`]
+    #[inline(always)]
+    pub fn vlan_header(
+        &mut self,
+    ) -> Result<AlignedMut<'_, vlan_hdr>, PacketError> {
+        self.header(self.vlan_off)
+    }
+
+    #[inline(always)]
+    pub fn arp_header(
+        &mut self,
+    ) -> Result<AlignedMut<'_, arphdr>, PacketError> {
+        self.l3_header(ETH_P_ARP)
+    }
+
+    #[inline(always)]
+    pub fn ipv4_header(
+        &mut self,
+    ) -> Result<AlignedMut<'_, iphdr>, PacketError> {
+        self.l3_header(ETH_P_IP)
+    }
+
+    #[inline(always)]
+    pub fn ipv6_header(
+        &mut self,
+    ) -> Result<AlignedMut<'_, ipv6hdr>, PacketError> {
+        self.l3_header(ETH_P_IPV6)
+    }
+
+    /// The IPv6 fragment header skipped by [`Self::ipv6_ext`]
+    #[inline(always)]
+    pub fn ipv6_frag_header(
+        &mut self,
+    ) -> Result<AlignedMut<'_, frag_hdr>, PacketError> {
+        self.header(self.frag_off)
+    }
+
+    #[inline(always)]
+    pub fn tcp_header(
+        &mut self,
+    ) -> Result<AlignedMut<'_, tcphdr>, PacketError> {
+        self.l4_header(IPPROTO_TCP)
+    }
+
+    #[inline(always)]
+    pub fn udp_header(
+        &mut self,
+    ) -> Result<AlignedMut<'_, udphdr>, PacketError> {
+        self.l4_header(IPPROTO_UDP)
+    }
+
+    #[inline(always)]
+    pub fn icmp_header(
+        &mut self,
+    ) -> Result<AlignedMut<'_, icmphdr>, PacketError> {
+        self.l4_header(IPPROTO_ICMP)
+    }
+
+    #[inline(always)]
+    pub fn icmp6_header(
+        &mut self,
+    ) -> Result<AlignedMut<'_, icmp6hdr>, PacketError> {
+        self.l4_header(IPPROTO_ICMPV6)
+    }
+
+    /// The rest of the packet after the last parsed header
+    #[inline(always)]
+    pub fn payload(&mut self) -> &mut [c_uchar] {
+        &mut self.data[self.off..]
+    }
+
+    /// Swaps the source and destination of every parsed layer, i.e. the MAC
+    /// addresses, the IPv4 or IPv6 addresses and the TCP or UDP ports. The
+    /// packet is left untouched if the L3 protocol is neither IPv4 nor IPv6.
+    #[inline(always)]
+    pub fn swap_endpoints(&mut self) -> Result<(), PacketError> {
+        // Checked first so that an error does not leave a half-swapped packet
+        if let Some((_, p)) = self.l3 {
+            if p as u32 != ETH_P_IP && p as u32 != ETH_P_IPV6 {
+                return Err(PacketError::UnexpectedProtocol);
+            }
+        }
+
+        if let Some(eth) = self.eth_off {
+            self.swap_bytes(
+                eth + core::mem::offset_of!(ethhdr, h_dest),
+                eth + core::mem::offset_of!(ethhdr, h_source),
+                ETH_ALEN,
+            );
+        }
+
+        // saddr and daddr are wrapped in a union, so their offsets cannot be
+        // taken with offset_of!
+        match self.l3 {
+            Some((ip, p)) if p as u32 == ETH_P_IP => {
+                self.swap_bytes(ip + 12, ip + 16, 4)
+            }
+            Some((ip, _)) => self.swap_bytes(ip + 8, ip + 24, 16),
+            None => {}
+        }
+
+        match self.l4 {
+            Some((l4, p))
+                if p as u32 == IPPROTO_TCP || p as u32 == IPPROTO_UDP =>
+            {
+                // source and dest are the first two fields of both headers
+                self.swap_bytes(l4, l4 + 2, 2)
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Turns the parsed request into a reply to its sender carrying the
+    /// first `payload_len` bytes of [`Self::payload`]: the endpoints are
+    /// swapped, the IP and UDP length fields are set for the new payload
+    /// and the IPv4 header checksum and the TCP, UDP, ICMP or ICMPv6
+    /// checksum are recomputed.
+    ///
+    /// ICMP and ICMPv6 echo requests become echo replies, the type of other
+    /// ICMP messages is left for the caller to set before calling this.
+    ///
+    /// Returns the new length of the packet, which the caller trims the
+    /// packet to, e.g. with `xdp::bpf_xdp_adjust_tail`. To reply with a
+    /// longer payload, grow the packet first and parse it again. The TCP
+    /// sequence numbers are left untouched.
+    pub fn reply_in_place(
+        &mut self,
+        payload_len: usize,
+    ) -> Result<usize, PacketError> {
+        let Some((ip, l3_proto)) = self.l3 else {
+            return Err(PacketError::NotParsed);
+        };
+        if payload_len > self.data.len() - self.off {
+            return Err(PacketError::Truncated);
+        }
+        let new_len = self.off + payload_len;
+
+        self.swap_endpoints()?;
+
+        let is_ipv4 = l3_proto as u32 == ETH_P_IP;
+        if is_ipv4 {
+            let ihl = (self.read_u8(ip) & 0xf) as usize * 4;
+            let check = ip + core::mem::offset_of!(iphdr, check);
+
+            self.write_be16(
+                ip + core::mem::offset_of!(iphdr, tot_len),
+                (new_len - ip) as u16,
+            );
+            self.write_csum(check, 0);
+            let sum = csum::csum_partial(&self.data[ip..ip + ihl], 0);
+            self.write_csum(check, csum::csum_fold(sum));
+        } else {
+            // Includes the extension headers
+            self.write_be16(
+                ip + core::mem::offset_of!(ipv6hdr, payload_len),
+                (new_len - ip - size_of::<ipv6hdr>()) as u16,
+            );
+        }
+
+        let Some((l4, l4_proto)) = self.l4 else {
+            return Ok(new_len);
+        };
+        let l4_len = new_len - l4;
+
+        // Pseudo-header sum of the new (i.e. already swapped) addresses,
+        // saddr and daddr are wrapped in a union, so their offsets cannot be
+        // taken with offset_of!
+        let pseudo_sum = |data: &[u8], proto: u8| {
+            if is_ipv4 {
+                let addr = |off: usize| {
+                    u32::from_ne_bytes([
+                        data[off],
+                        data[off + 1],
+                        data[off + 2],
+                        data[off + 3],
+                    ])
+                };
+                csum::ipv4_pseudo_hdr_sum(
+                    addr(ip + 12),
+                    addr(ip + 16),
+                    proto,
+                    l4_len as u16,
+                )
+            } else {
+                let addr = |off: usize| {
+                    let mut addr = [0u8; 16];
+                    addr.copy_from_slice(&data[off..off + 16]);
+                    addr
+                };
+                csum::ipv6_pseudo_hdr_sum(
+                    &addr(ip + 8),
+                    &addr(ip + 24),
+                    proto,
+                    l4_len as u32,
+                )
+            }
+        };
+
+        let (check, sum, is_udp) = match l4_proto as u32 {
+            IPPROTO_UDP => {
+                self.write_be16(
+                    l4 + core::mem::offset_of!(udphdr, len),
+                    l4_len as u16,
+                );
+                let check = l4 + core::mem::offset_of!(udphdr, check);
+                (check, pseudo_sum(self.data, l4_proto), true)
+            }
+            IPPROTO_TCP => {
+                let check = l4 + core::mem::offset_of!(tcphdr, check);
+                (check, pseudo_sum(self.data, l4_proto), false)
+            }
+            IPPROTO_ICMPV6 => {
+                if self.read_u8(l4) as u32 == ICMPV6_ECHO_REQUEST {
+                    self.write_u8(l4, ICMPV6_ECHO_REPLY as u8);
+                }
+                let check = l4 + core::mem::offset_of!(icmp6hdr, icmp6_cksum);
+                (check, pseudo_sum(self.data, l4_proto), false)
+            }
+            // ICMP has no pseudo-header
+            _ => {
+                if self.read_u8(l4) as u32 == ICMP_ECHO {
+                    self.write_u8(l4, ICMP_ECHOREPLY as u8);
+                }
+                (l4 + core::mem::offset_of!(icmphdr, checksum), 0, false)
+            }
+        };
+
+        self.write_csum(check, 0);
+        let sum = csum::csum_partial(&self.data[l4..new_len], sum);
+        let check_val = match csum::csum_fold(sum) {
+            // A zero UDP checksum means no checksum
+            0 if is_udp => 0xffff,
+            val => val,
+        };
+        self.write_csum(check, check_val);
+
+        Ok(new_len)
+    }
+}
+
+/// Cursor past the Ethernet header and up to two (QinQ) VLAN tags of `data`,
+/// i.e. at the network header. Used by the header accessors of XDP and TC.
+#[inline(always)]
+pub(crate) fn l3_cursor(data: &mut [c_uchar]) -> Option<PacketCursor<'_>> {
+    PacketCursor::new(data)
+        .eth()
+        .and_then(|c| c.vlan())
+        .and_then(|c| c.vlan())
+        .ok()
+}
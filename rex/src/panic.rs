@@ -109,6 +109,13 @@ impl<'a> CleanupEntries<'a> {
         idx
     }
 
+    /// This function is called by object constructors that only obtain the
+    /// object after registering its entry, e.g. from a lookup helper
+    pub(crate) fn set_cleanup_arg(idx: usize, cleanup_arg: *mut ()) {
+        let entries = Self::this_cpu_cleanup_entries();
+        entries.entries[idx].cleanup_arg = cleanup_arg;
+    }
+
     /// This function is called by the object drop handler. It invalidates the
     /// entry corresponding to the object.
     pub(crate) fn deregister_cleanup(idx: usize) {
@@ -3,24 +3,66 @@ use core::{mem, slice};
 
 use crate::base_helper::termination_check;
 use crate::bindings::linux::kernel::{
-    ethhdr, iphdr, sk_buff, sock, tcphdr, udphdr,
+    arphdr, ethhdr, frag_hdr, icmp6hdr, icmphdr, iphdr, ipv6_opt_hdr, ipv6hdr,
+    sk_buff, skb_shared_info, sock, tcphdr, udphdr, vlan_hdr,
 };
 use crate::bindings::uapi::linux::bpf::bpf_map_type;
+pub use crate::bindings::uapi::linux::bpf::{
+    BPF_F_HDR_FIELD_MASK, BPF_F_MARK_ENFORCE, BPF_F_MARK_MANGLED_0,
+    BPF_F_PSEUDO_HDR,
+};
 pub use crate::bindings::uapi::linux::pkt_cls::{
     TC_ACT_OK, TC_ACT_REDIRECT, TC_ACT_SHOT,
 };
-use crate::ffi;
+use crate::conntrack::{Conntrack, ConntrackInit, CtOpts};
+use crate::fib::{FibLookupParams, FibLookupResult};
+use crate::packet::{
+    self, PacketCursor, IPPROTO_DSTOPTS, IPPROTO_FRAGMENT, IPPROTO_HOPOPTS,
+    IPPROTO_ROUTING, MAX_IPV6_EXT_HDRS,
+};
 use crate::prog_type::rex_prog;
+use crate::socket::{rex_sock_guard, SockTuple};
 use crate::utils::*;
+use crate::{conntrack, ffi, fib, socket};
 
 pub struct __sk_buff<'a> {
     pub data_slice: &'a mut [c_uchar],
-    kptr: &'static mut sk_buff,
+    pub(crate) kptr: &'static mut sk_buff,
 }
 
 // Define accessors of program-accessible fields
 // TODO: may need to append more based on __sk_buff
 impl<'a> __sk_buff<'a> {
+    /// Wraps the `struct sk_buff` passed as the program context, shared by
+    /// all program types operating on an `sk_buff`
+    #[inline(always)]
+    pub(crate) fn from_ctx(ctx: *mut ()) -> Self {
+        let kptr = unsafe { &mut *(ctx as *mut sk_buff) };
+
+        // NOTE: not support jumobo frame yet with non-linear sk_buff
+        let data_length = (kptr.len - kptr.data_len) as usize;
+
+        let data_slice = unsafe {
+            slice::from_raw_parts_mut(kptr.data as *mut c_uchar, data_length)
+        };
+
+        __sk_buff { data_slice, kptr }
+    }
+
+    /// Re-derives `data_slice` from the kernel `sk_buff`, needed after a
+    /// helper that may move the packet data
+    #[inline(always)]
+    pub(crate) fn update_slice(&mut self) {
+        let data_length = (self.kptr.len - self.kptr.data_len) as usize;
+
+        self.data_slice = unsafe {
+            slice::from_raw_parts_mut(
+                self.kptr.data as *mut c_uchar,
+                data_length,
+            )
+        };
+    }
+
     #[inline(always)]
     pub fn len(&self) -> u32 {
         self.kptr.len
@@ -158,12 +200,223 @@ impl<'a> __sk_buff<'a> {
         0
     }
 
+    /// Metadata in front of the packet data, set by an XDP program with
+    /// `bpf_xdp_adjust_meta`
+    #[inline(always)]
+    pub fn meta_slice(&self) -> &[c_uchar] {
+        let shinfo = unsafe {
+            &*(self.kptr.head.add(self.kptr.end as usize)
+                as *const skb_shared_info)
+        };
+        let meta_len = shinfo.meta_len as usize;
+
+        unsafe {
+            slice::from_raw_parts(
+                self.kptr.data.sub(meta_len) as *const c_uchar,
+                meta_len,
+            )
+        }
+    }
+
     #[inline(always)]
     pub fn sk(&self) -> &'a sock {
         unsafe { &*self.kptr.sk }
     }
 }
 
+/// Offset of the header at `header` bytes from `skb->head` in the linear data
+/// of the `sk_buff`, `None` if the header is not set or is before the data
+#[inline(always)]
+pub(crate) fn skb_header_offset(skb: &sk_buff, header: u16) -> Option<usize> {
+    // (typeof(skb->transport_header))~0U marks an unset header
+    if header == u16::MAX {
+        return None;
+    }
+
+    (skb.head as usize + header as usize).checked_sub(skb.data as usize)
+}
+
+/// Network and transport header offsets of the `sk_buff`
+#[inline(always)]
+pub(crate) fn skb_net_header_offsets(
+    skb: &sk_buff,
+) -> (Option<usize>, Option<usize>) {
+    let headers = unsafe { (skb.__bindgen_anon_4.__bindgen_anon_1).as_ref() };
+    (
+        skb_header_offset(skb, headers.network_header),
+        skb_header_offset(skb, headers.transport_header),
+    )
+}
+
+/// Transport header offset of the `sk_buff` if the IPv4 or IPv6 header
+/// before it announces `proto` (`IPPROTO_*`). IPv6 extension headers between
+/// the two are walked to find the protocol of the transport header, `None`
+/// is returned for non-first fragments, which have no transport header.
+#[inline(always)]
+pub(crate) fn skb_l4_header_offset(
+    skb: &sk_buff,
+    data: &[c_uchar],
+    proto: u32,
+) -> Option<usize> {
+    let (l3, l4) = skb_net_header_offsets(skb);
+    let (l3, l4) = (l3?, l4?);
+    let read_be16 = |off: usize| {
+        data.get(off..off + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+    };
+
+    let l4_proto = match data.get(l3)? >> 4 {
+        4 => {
+            // Only the first fragment carries the transport header
+            if read_be16(l3 + mem::offset_of!(iphdr, frag_off))? & 0x1fff != 0 {
+                return None;
+            }
+            *data.get(l3 + mem::offset_of!(iphdr, protocol))?
+        }
+        6 => {
+            let mut next = *data.get(l3 + mem::offset_of!(ipv6hdr, nexthdr))?;
+            let mut off = l3 + mem::size_of::<ipv6hdr>();
+
+            for _ in 0..MAX_IPV6_EXT_HDRS {
+                if off >= l4 {
+                    break;
+                }
+
+                let len = match next as u32 {
+                    IPPROTO_HOPOPTS | IPPROTO_ROUTING | IPPROTO_DSTOPTS => {
+                        let hdrlen = *data
+                            .get(off + mem::offset_of!(ipv6_opt_hdr, hdrlen))?;
+                        (hdrlen as usize + 1) * 8
+                    }
+                    IPPROTO_FRAGMENT => {
+                        let frag_off = read_be16(
+                            off + mem::offset_of!(frag_hdr, frag_off),
+                        )?;
+                        if frag_off & 0xfff8 != 0 {
+                            return None;
+                        }
+                        mem::size_of::<frag_hdr>()
+                    }
+                    _ => return None,
+                };
+
+                // All extension headers start with the next header field
+                next = *data.get(off)?;
+                off += len;
+            }
+
+            if off != l4 {
+                return None;
+            }
+            next
+        }
+        _ => return None,
+    };
+
+    (l4_proto as u32 == proto).then_some(l4)
+}
+
+// Read-only L3/L4 header accessors for program types whose skb data does not
+// always start at the MAC header. The headers are located with the network
+// and transport header offsets of the kernel `sk_buff`, which `$skb` returns
+// for the context, and are `None` if they are not in the linear data. The L4
+// accessors also return `None` if the IP header announces another protocol.
+macro_rules! skb_net_header_defs {
+    ($ctx:ty, $skb:expr) => {
+        #[inline(always)]
+        pub fn ip_header<'b>(
+            &self,
+            ctx: &'b $ctx,
+        ) -> Option<
+            crate::utils::Aligned<'b, crate::bindings::linux::kernel::iphdr>,
+        > {
+            let (l3, _) = crate::sched_cls::skb_net_header_offsets(($skb)(ctx));
+            let l3 = l3?;
+            if ctx.data_slice.get(l3)? >> 4 != 4 {
+                return None;
+            }
+            crate::utils::convert_slice_at_to_struct(&ctx.data_slice[..], l3)
+        }
+
+        #[inline(always)]
+        pub fn ipv6_header<'b>(
+            &self,
+            ctx: &'b $ctx,
+        ) -> Option<
+            crate::utils::Aligned<'b, crate::bindings::linux::kernel::ipv6hdr>,
+        > {
+            let (l3, _) = crate::sched_cls::skb_net_header_offsets(($skb)(ctx));
+            let l3 = l3?;
+            if ctx.data_slice.get(l3)? >> 4 != 6 {
+                return None;
+            }
+            crate::utils::convert_slice_at_to_struct(&ctx.data_slice[..], l3)
+        }
+
+        #[inline(always)]
+        pub fn tcp_header<'b>(
+            &self,
+            ctx: &'b $ctx,
+        ) -> Option<
+            crate::utils::Aligned<'b, crate::bindings::linux::kernel::tcphdr>,
+        > {
+            let l4 = crate::sched_cls::skb_l4_header_offset(
+                ($skb)(ctx),
+                &ctx.data_slice[..],
+                crate::packet::IPPROTO_TCP,
+            )?;
+            crate::utils::convert_slice_at_to_struct(&ctx.data_slice[..], l4)
+        }
+
+        #[inline(always)]
+        pub fn udp_header<'b>(
+            &self,
+            ctx: &'b $ctx,
+        ) -> Option<
+            crate::utils::Aligned<'b, crate::bindings::linux::kernel::udphdr>,
+        > {
+            let l4 = crate::sched_cls::skb_l4_header_offset(
+                ($skb)(ctx),
+                &ctx.data_slice[..],
+                crate::packet::IPPROTO_UDP,
+            )?;
+            crate::utils::convert_slice_at_to_struct(&ctx.data_slice[..], l4)
+        }
+
+        #[inline(always)]
+        pub fn icmp_header<'b>(
+            &self,
+            ctx: &'b $ctx,
+        ) -> Option<
+            crate::utils::Aligned<'b, crate::bindings::linux::kernel::icmphdr>,
+        > {
+            let l4 = crate::sched_cls::skb_l4_header_offset(
+                ($skb)(ctx),
+                &ctx.data_slice[..],
+                crate::packet::IPPROTO_ICMP,
+            )?;
+            crate::utils::convert_slice_at_to_struct(&ctx.data_slice[..], l4)
+        }
+
+        #[inline(always)]
+        pub fn icmp6_header<'b>(
+            &self,
+            ctx: &'b $ctx,
+        ) -> Option<
+            crate::utils::Aligned<'b, crate::bindings::linux::kernel::icmp6hdr>,
+        > {
+            let l4 = crate::sched_cls::skb_l4_header_offset(
+                ($skb)(ctx),
+                &ctx.data_slice[..],
+                crate::packet::IPPROTO_ICMPV6,
+            )?;
+            crate::utils::convert_slice_at_to_struct(&ctx.data_slice[..], l4)
+        }
+    };
+}
+
+pub(crate) use skb_net_header_defs;
+
 /// prog_fn should have &Self as its first argument
 #[repr(C)]
 pub struct sched_cls {
@@ -190,6 +443,34 @@ impl sched_cls {
         )
     }
 
+    /// The outermost VLAN tag, `None` if the packet is untagged
+    #[inline(always)]
+    pub fn vlan_header<'b>(
+        &self,
+        skb: &'b mut __sk_buff,
+    ) -> Option<AlignedMut<'b, vlan_hdr>> {
+        let cursor = PacketCursor::new(&mut skb.data_slice).eth().ok()?;
+        let off = cursor.offset();
+        if cursor.vlan().ok()?.offset() == off {
+            return None;
+        }
+
+        convert_slice_at_to_struct_mut(&mut skb.data_slice[..], off)
+    }
+
+    /// The ARP header after the VLAN tags, `None` if the packet is not ARP
+    #[inline(always)]
+    pub fn arp_header<'b>(
+        &self,
+        skb: &'b mut __sk_buff,
+    ) -> Option<AlignedMut<'b, arphdr>> {
+        let cursor = packet::l3_cursor(&mut skb.data_slice)?;
+        let off = cursor.offset();
+        cursor.arp().ok()?;
+
+        convert_slice_at_to_struct_mut(&mut skb.data_slice[..], off)
+    }
+
     #[inline(always)]
     pub fn udp_header<'b>(
         &self,
@@ -226,6 +507,50 @@ impl sched_cls {
         convert_slice_to_struct_mut::<iphdr>(&mut skb.data_slice[begin..end])
     }
 
+    /// The ICMP header after the IPv4 header and its options, `None` if the
+    /// packet is not ICMP
+    #[inline(always)]
+    pub fn icmp_header<'b>(
+        &self,
+        skb: &'b mut __sk_buff,
+    ) -> Option<AlignedMut<'b, icmphdr>> {
+        let cursor = packet::l3_cursor(&mut skb.data_slice)?.ipv4().ok()?;
+        let off = cursor.offset();
+        cursor.icmp().ok()?;
+
+        convert_slice_at_to_struct_mut(&mut skb.data_slice[..], off)
+    }
+
+    /// The IPv6 header after the VLAN tags, `None` if the packet is not IPv6
+    #[inline(always)]
+    pub fn ipv6_header<'b>(
+        &self,
+        skb: &'b mut __sk_buff,
+    ) -> Option<AlignedMut<'b, ipv6hdr>> {
+        let cursor = packet::l3_cursor(&mut skb.data_slice)?;
+        let off = cursor.offset();
+        cursor.ipv6().ok()?;
+
+        convert_slice_at_to_struct_mut(&mut skb.data_slice[..], off)
+    }
+
+    /// The ICMPv6 header after the IPv6 extension headers, `None` if the
+    /// packet is not ICMPv6
+    #[inline(always)]
+    pub fn icmp6_header<'b>(
+        &self,
+        skb: &'b mut __sk_buff,
+    ) -> Option<AlignedMut<'b, icmp6hdr>> {
+        let cursor = packet::l3_cursor(&mut skb.data_slice)?
+            .ipv6()
+            .and_then(|c| c.ipv6_ext())
+            .ok()?;
+        let off = cursor.offset();
+        cursor.icmp6().ok()?;
+
+        convert_slice_at_to_struct_mut(&mut skb.data_slice[..], off)
+    }
+
     #[inline(always)]
     pub fn bpf_clone_redirect(
         &self,
@@ -252,22 +577,217 @@ impl sched_cls {
         Ok(0)
     }
 
+    /// Looks up the route of a packet in the kernel FIB, e.g. to fill in the
+    /// MAC addresses before redirecting it to the egress device
+    #[inline(always)]
+    pub fn bpf_fib_lookup(
+        &self,
+        skb: &mut __sk_buff,
+        params: &FibLookupParams,
+        flags: u32,
+    ) -> core::result::Result<FibLookupResult, i32> {
+        fib::fib_lookup(params, |raw, plen| unsafe {
+            ffi::bpf_skb_fib_lookup(skb.kptr as *mut sk_buff, raw, plen, flags)
+        })
+    }
+
+    /// Looks up a TCP socket (full socket only) matching `tuple` in the
+    /// network namespace of the device of the packet. The reference
+    /// is released when the returned guard is dropped.
+    #[inline(always)]
+    pub fn bpf_sk_lookup_tcp(
+        &self,
+        skb: &mut __sk_buff,
+        tuple: &SockTuple,
+    ) -> Option<rex_sock_guard> {
+        rex_sock_guard::lookup(tuple, |raw, size| unsafe {
+            ffi::bpf_sk_lookup_tcp(
+                skb.kptr as *mut sk_buff,
+                raw,
+                size,
+                socket::CURRENT_NETNS_SKB,
+                0,
+            )
+        })
+    }
+
+    /// Looks up a UDP socket matching `tuple` in the network namespace of
+    /// the device the packet arrived on. The reference is released when the
+    /// returned guard is dropped.
+    #[inline(always)]
+    pub fn bpf_sk_lookup_udp(
+        &self,
+        skb: &mut __sk_buff,
+        tuple: &SockTuple,
+    ) -> Option<rex_sock_guard> {
+        rex_sock_guard::lookup(tuple, |raw, size| unsafe {
+            ffi::bpf_sk_lookup_udp(
+                skb.kptr as *mut sk_buff,
+                raw,
+                size,
+                socket::CURRENT_NETNS_SKB,
+                0,
+            )
+        })
+    }
+
+    /// Like [`Self::bpf_sk_lookup_tcp`], but may also return a request or
+    /// time-wait socket, e.g. to check a SYN cookie against the listener
+    #[inline(always)]
+    pub fn bpf_skc_lookup_tcp(
+        &self,
+        skb: &mut __sk_buff,
+        tuple: &SockTuple,
+    ) -> Option<rex_sock_guard> {
+        rex_sock_guard::lookup(tuple, |raw, size| unsafe {
+            ffi::bpf_skc_lookup_tcp(
+                skb.kptr as *mut sk_buff,
+                raw,
+                size,
+                socket::CURRENT_NETNS_SKB,
+                0,
+            )
+        })
+    }
+
+    /// Looks up the conntrack entry matching `tuple` in the network
+    /// namespace of the packet, the error (e.g. `-ENOENT`) is returned if
+    /// there is none. The reference is released when the returned guard is
+    /// dropped.
+    #[inline(always)]
+    pub fn bpf_ct_lookup(
+        &self,
+        skb: &mut __sk_buff,
+        tuple: &SockTuple,
+        opts: &CtOpts,
+    ) -> core::result::Result<Conntrack, i32> {
+        let (ct, cleanup_idx, dir) = conntrack::ct_acquire(
+            tuple,
+            opts,
+            |raw_tuple, tuple_sz, raw_opts, opts_sz| unsafe {
+                ffi::bpf_skb_ct_lookup(
+                    skb.kptr as *mut sk_buff,
+                    raw_tuple,
+                    tuple_sz,
+                    raw_opts,
+                    opts_sz,
+                )
+            },
+        )?;
+
+        Ok(Conntrack::new(ct, cleanup_idx, dir))
+    }
+
+    /// Allocates a conntrack entry for `tuple`, which can be inserted into
+    /// the table with [`ConntrackInit::insert`] after setting its timeout
+    /// and status
+    #[inline(always)]
+    pub fn bpf_ct_alloc(
+        &self,
+        skb: &mut __sk_buff,
+        tuple: &SockTuple,
+        opts: &CtOpts,
+    ) -> core::result::Result<ConntrackInit, i32> {
+        let (ct, cleanup_idx, _) = conntrack::ct_acquire(
+            tuple,
+            opts,
+            |raw_tuple, tuple_sz, raw_opts, opts_sz| unsafe {
+                ffi::bpf_skb_ct_alloc(
+                    skb.kptr as *mut sk_buff,
+                    raw_tuple,
+                    tuple_sz,
+                    raw_opts,
+                    opts_sz,
+                )
+            },
+        )?;
+
+        Ok(ConntrackInit::new(ct, cleanup_idx))
+    }
+
+    /// Computes the checksum difference between `from` and `to`, added to
+    /// `seed`. The result can be passed to [`Self::bpf_l3_csum_replace`] or
+    /// [`Self::bpf_l4_csum_replace`] with `from = 0` and a size of 0, e.g.
+    /// for fields longer than 4 bytes like IPv6 addresses.
+    #[inline(always)]
+    pub fn bpf_csum_diff(
+        &self,
+        from: &[u32],
+        to: &[u32],
+        seed: u32,
+    ) -> core::result::Result<u32, i32> {
+        let ret = termination_check!(unsafe {
+            ffi::bpf_csum_diff(
+                from.as_ptr(),
+                (from.len() * core::mem::size_of::<u32>()) as u32,
+                to.as_ptr(),
+                (to.len() * core::mem::size_of::<u32>()) as u32,
+                seed,
+            )
+        });
+
+        if ret < 0 {
+            Err(ret as i32)
+        } else {
+            Ok(ret as u32)
+        }
+    }
+
+    /// Updates the L3 (IP) checksum at `offset` of the packet after a field
+    /// changed from `from` to `to`. The low 4 bits of `flags`
+    /// ([`BPF_F_HDR_FIELD_MASK`]) are the size of the field, i.e. 2 or 4.
+    #[inline(always)]
+    pub fn bpf_l3_csum_replace(
+        &self,
+        skb: &mut __sk_buff,
+        offset: u32,
+        from: u64,
+        to: u64,
+        flags: u64,
+    ) -> Result {
+        let ret = termination_check!(unsafe {
+            to_result!(ffi::bpf_l3_csum_replace(
+                skb.kptr, offset, from, to, flags
+            ))
+        });
+
+        // The helper may have to make the packet data writable first
+        skb.update_slice();
+        ret
+    }
+
+    /// Updates the L4 (TCP/UDP) checksum at `offset` of the packet after a
+    /// field changed from `from` to `to`. The low 4 bits of `flags`
+    /// ([`BPF_F_HDR_FIELD_MASK`]) are the size of the field,
+    /// [`BPF_F_PSEUDO_HDR`] should be set if the field is part of the
+    /// pseudo-header (e.g. an IP address) and [`BPF_F_MARK_MANGLED_0`] keeps
+    /// a zero UDP checksum untouched.
+    #[inline(always)]
+    pub fn bpf_l4_csum_replace(
+        &self,
+        skb: &mut __sk_buff,
+        offset: u32,
+        from: u64,
+        to: u64,
+        flags: u64,
+    ) -> Result {
+        let ret = termination_check!(unsafe {
+            to_result!(ffi::bpf_l4_csum_replace(
+                skb.kptr, offset, from, to, flags
+            ))
+        });
+
+        skb.update_slice();
+        ret
+    }
+
     // Now returns a mutable ref, but since every reg is private the user prog
     // cannot change reg contents. The user should not be able to directly
     // assign this reference a new value either, given that they will not able
     // to create another instance of pt_regs (private fields, no pub ctor)
     #[inline(always)]
     fn convert_ctx(&self, ctx: *mut ()) -> __sk_buff {
-        let kptr = unsafe { &mut *(ctx as *mut sk_buff) };
-
-        // NOTE: not support jumobo frame yet with non-linear sk_buff
-        let data_length = (kptr.len - kptr.data_len) as usize;
-
-        let data_slice = unsafe {
-            slice::from_raw_parts_mut(kptr.data as *mut c_uchar, data_length)
-        };
-
-        __sk_buff { data_slice, kptr }
+        __sk_buff::from_ctx(ctx)
     }
 }
 
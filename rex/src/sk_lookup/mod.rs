@@ -0,0 +1,3 @@
+mod sk_lookup_impl;
+
+pub use sk_lookup_impl::*;
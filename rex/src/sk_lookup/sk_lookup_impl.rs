@@ -0,0 +1,177 @@
+use core::intrinsics::unlikely;
+
+use crate::base_helper::termination_check;
+use crate::bindings::linux::kernel::{bpf_sk_lookup_kern, sock};
+use crate::bindings::uapi::linux::bpf::bpf_map_type;
+// expose the following constants to the user
+pub use crate::bindings::uapi::linux::bpf::{
+    BPF_SK_LOOKUP_F_NO_REUSEPORT, BPF_SK_LOOKUP_F_REPLACE, SK_DROP, SK_PASS,
+};
+use crate::ffi;
+use crate::linux::errno::{EINVAL, ENOENT};
+use crate::map::{RexSockHash, RexSockMap};
+use crate::prog_type::rex_prog;
+use crate::socket::rex_sock_guard;
+use crate::utils::*;
+
+pub struct bpf_sk_lookup {
+    kptr: &'static mut bpf_sk_lookup_kern,
+}
+
+// Define accessors of program-accessible fields
+impl bpf_sk_lookup {
+    #[inline(always)]
+    pub fn family(&self) -> u16 {
+        self.kptr.family
+    }
+
+    /// IP protocol, i.e. `IPPROTO_TCP` or `IPPROTO_UDP`
+    #[inline(always)]
+    pub fn protocol(&self) -> u16 {
+        self.kptr.protocol
+    }
+
+    #[inline(always)]
+    pub fn remote_ip4(&self) -> u32be {
+        u32be(self.kptr.v4.saddr)
+    }
+
+    #[inline(always)]
+    pub fn local_ip4(&self) -> u32be {
+        u32be(self.kptr.v4.daddr)
+    }
+
+    /// `None` if the packet is not IPv6
+    #[inline(always)]
+    pub fn remote_ip6(&self) -> Option<[u32; 4]> {
+        let addr = self.kptr.v6.saddr;
+        if addr.is_null() {
+            None
+        } else {
+            Some(unsafe { (*addr).in6_u.u6_addr32 })
+        }
+    }
+
+    /// `None` if the packet is not IPv6
+    #[inline(always)]
+    pub fn local_ip6(&self) -> Option<[u32; 4]> {
+        let addr = self.kptr.v6.daddr;
+        if addr.is_null() {
+            None
+        } else {
+            Some(unsafe { (*addr).in6_u.u6_addr32 })
+        }
+    }
+
+    #[inline(always)]
+    pub fn remote_port(&self) -> u16be {
+        u16be(self.kptr.sport)
+    }
+
+    /// In host byte order, unlike [`Self::remote_port`]
+    #[inline(always)]
+    pub fn local_port(&self) -> u16 {
+        self.kptr.dport
+    }
+
+    #[inline(always)]
+    pub fn ingress_ifindex(&self) -> u32 {
+        self.kptr.ingress_ifindex
+    }
+}
+
+/// prog_fn should have &Self as its first argument
+///
+/// The program returns `Ok(SK_PASS)` to use the socket selected by
+/// `bpf_sk_assign` (or to continue the regular lookup if none is selected)
+/// and `Ok(SK_DROP)` to drop the packet. Returning an `Err` also drops the
+/// packet.
+#[repr(C)]
+pub struct sk_lookup {
+    prog: fn(&Self, &mut bpf_sk_lookup) -> Result,
+}
+
+impl sk_lookup {
+    crate::base_helper::base_helper_defs!();
+
+    pub const unsafe fn new(
+        f: fn(&sk_lookup, &mut bpf_sk_lookup) -> Result,
+    ) -> sk_lookup {
+        Self { prog: f }
+    }
+
+    // The socket returned by a sockmap/sockhash lookup holds a reference,
+    // which is dropped with the guard right after the assignment
+    #[inline(always)]
+    fn assign(
+        ctx: &mut bpf_sk_lookup,
+        sk: Option<rex_sock_guard>,
+        flags: u64,
+    ) -> Result {
+        let sk = sk.ok_or(-(ENOENT as i32))?;
+
+        termination_check!(unsafe {
+            to_result!(ffi::bpf_sk_lookup_assign(ctx.kptr, sk.as_ptr(), flags))
+        })
+    }
+
+    /// Selects the socket at `key` in `map` to receive the packet.
+    ///
+    /// `BPF_SK_LOOKUP_F_REPLACE` allows overriding a previous selection and
+    /// `BPF_SK_LOOKUP_F_NO_REUSEPORT` skips the reuseport group of the
+    /// socket.
+    #[inline(always)]
+    pub fn bpf_sk_assign(
+        &self,
+        ctx: &mut bpf_sk_lookup,
+        map: &'static RexSockMap,
+        key: u32,
+        flags: u64,
+    ) -> Result {
+        let map_kptr = unsafe { core::ptr::read_volatile(&map.kptr) };
+        if unlikely(map_kptr.is_null()) {
+            return Err(-(EINVAL as i32));
+        }
+
+        let sk = rex_sock_guard::acquire(|| unsafe {
+            ffi::bpf_map_lookup_elem(map_kptr, &key as *const u32 as *const ())
+                as *mut sock
+        });
+        Self::assign(ctx, sk, flags)
+    }
+
+    /// Same as [`Self::bpf_sk_assign`] but for a sockhash
+    #[inline(always)]
+    pub fn bpf_sk_assign_hash<K>(
+        &self,
+        ctx: &mut bpf_sk_lookup,
+        map: &'static RexSockHash<K>,
+        key: &K,
+        flags: u64,
+    ) -> Result {
+        let map_kptr = unsafe { core::ptr::read_volatile(&map.kptr) };
+        if unlikely(map_kptr.is_null()) {
+            return Err(-(EINVAL as i32));
+        }
+
+        let sk = rex_sock_guard::acquire(|| unsafe {
+            ffi::bpf_map_lookup_elem(map_kptr, key as *const K as *const ())
+                as *mut sock
+        });
+        Self::assign(ctx, sk, flags)
+    }
+
+    #[inline(always)]
+    fn convert_ctx(&self, ctx: *mut ()) -> bpf_sk_lookup {
+        let kptr = unsafe { &mut *(ctx as *mut bpf_sk_lookup_kern) };
+        bpf_sk_lookup { kptr }
+    }
+}
+
+impl rex_prog for sk_lookup {
+    fn prog_run(&self, ctx: *mut ()) -> u32 {
+        let mut newctx = self.convert_ctx(ctx);
+        // drop the packet if error
+        ((self.prog)(self, &mut newctx)).unwrap_or(SK_DROP as i32) as u32
+    }
+}
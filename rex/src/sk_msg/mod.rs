@@ -0,0 +1,3 @@
+mod sk_msg_impl;
+
+pub use sk_msg_impl::*;
@@ -0,0 +1,199 @@
+use core::ffi::c_uchar;
+use core::intrinsics::unlikely;
+use core::slice;
+
+use crate::base_helper::termination_check;
+use crate::bindings::linux::kernel::{sk_msg as sk_msg_kern, sock};
+use crate::bindings::uapi::linux::bpf::bpf_map_type;
+// expose the following constants to the user
+pub use crate::bindings::uapi::linux::bpf::{BPF_F_INGRESS, SK_DROP, SK_PASS};
+use crate::ffi;
+use crate::map::{RexSockHash, RexSockMap};
+use crate::prog_type::rex_prog;
+use crate::utils::*;
+
+pub struct sk_msg_md<'a> {
+    /// Data of the current scatterlist element, use `bpf_msg_apply_bytes`
+    /// and `bpf_msg_cork_bytes` to control how much of the message the
+    /// program sees
+    pub data_slice: &'a mut [c_uchar],
+    kptr: &'static mut sk_msg_kern,
+}
+
+// Define accessors of program-accessible fields
+impl<'a> sk_msg_md<'a> {
+    /// Total size of the message
+    #[inline(always)]
+    pub fn size(&self) -> u32 {
+        self.kptr.sg.size
+    }
+
+    #[inline(always)]
+    pub fn family(&self) -> u16 {
+        self.sk().__sk_common.skc_family
+    }
+
+    #[inline(always)]
+    pub fn remote_ip4(&self) -> u32be {
+        u32be(unsafe {
+            self.sk()
+                .__sk_common
+                .__bindgen_anon_1
+                .__bindgen_anon_1
+                .skc_daddr
+        })
+    }
+
+    #[inline(always)]
+    pub fn local_ip4(&self) -> u32be {
+        u32be(unsafe {
+            self.sk()
+                .__sk_common
+                .__bindgen_anon_1
+                .__bindgen_anon_1
+                .skc_rcv_saddr
+        })
+    }
+
+    #[inline(always)]
+    pub fn remote_port(&self) -> u16be {
+        u16be(unsafe {
+            self.sk()
+                .__sk_common
+                .__bindgen_anon_3
+                .__bindgen_anon_1
+                .skc_dport
+        })
+    }
+
+    /// In host byte order, unlike [`Self::remote_port`]
+    #[inline(always)]
+    pub fn local_port(&self) -> u16 {
+        unsafe {
+            self.sk()
+                .__sk_common
+                .__bindgen_anon_3
+                .__bindgen_anon_1
+                .skc_num
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn sk(&self) -> &'a sock {
+        unsafe { &*self.kptr.sk }
+    }
+}
+
+/// prog_fn should have &Self as its first argument
+///
+/// The program returns `Ok(SK_PASS)` to let the message through (or to
+/// perform the redirect set up by the redirect helpers) and `Ok(SK_DROP)` to
+/// drop it. Like other program types, an `Err` carries the verdict as well.
+#[repr(C)]
+pub struct sk_msg {
+    prog: fn(&Self, &mut sk_msg_md) -> Result,
+}
+
+impl sk_msg {
+    crate::base_helper::base_helper_defs!();
+
+    pub const unsafe fn new(
+        f: fn(&sk_msg, &mut sk_msg_md) -> Result,
+    ) -> sk_msg {
+        Self { prog: f }
+    }
+
+    /// Redirects the message to the socket at `key` in `map`, the direction
+    /// is egress unless `BPF_F_INGRESS` is set in `flags`.
+    ///
+    /// Returns `Ok(SK_PASS)` on success and `Err(SK_DROP)` on failure, which
+    /// can be returned from the program as is.
+    #[inline(always)]
+    pub fn bpf_msg_redirect_map(
+        &self,
+        msg: &mut sk_msg_md,
+        map: &'static RexSockMap,
+        key: u32,
+        flags: u64,
+    ) -> Result {
+        let map_kptr = unsafe { core::ptr::read_volatile(&map.kptr) };
+        if unlikely(map_kptr.is_null()) {
+            return Err(SK_DROP as i32);
+        }
+
+        let ret = termination_check!(unsafe {
+            ffi::bpf_msg_redirect_map(msg.kptr, map_kptr, key, flags)
+        });
+
+        if ret == SK_PASS as i64 {
+            Ok(SK_PASS as i32)
+        } else {
+            Err(SK_DROP as i32)
+        }
+    }
+
+    /// Same as [`Self::bpf_msg_redirect_map`] but for a sockhash
+    #[inline(always)]
+    pub fn bpf_msg_redirect_hash<K>(
+        &self,
+        msg: &mut sk_msg_md,
+        map: &'static RexSockHash<K>,
+        key: &K,
+        flags: u64,
+    ) -> Result {
+        let map_kptr = unsafe { core::ptr::read_volatile(&map.kptr) };
+        if unlikely(map_kptr.is_null()) {
+            return Err(SK_DROP as i32);
+        }
+
+        let ret = termination_check!(unsafe {
+            ffi::bpf_msg_redirect_hash(
+                msg.kptr,
+                map_kptr,
+                key as *const K as *const (),
+                flags,
+            )
+        });
+
+        if ret == SK_PASS as i64 {
+            Ok(SK_PASS as i32)
+        } else {
+            Err(SK_DROP as i32)
+        }
+    }
+
+    /// Applies the verdict of the program to the next `bytes` bytes of the
+    /// message, the program will not run again until they are consumed
+    #[inline(always)]
+    pub fn bpf_msg_apply_bytes(&self, msg: &mut sk_msg_md, bytes: u32) {
+        termination_check!(unsafe {
+            ffi::bpf_msg_apply_bytes(msg.kptr, bytes)
+        });
+    }
+
+    /// Holds off the verdict until at least `bytes` bytes of the message are
+    /// accumulated
+    #[inline(always)]
+    pub fn bpf_msg_cork_bytes(&self, msg: &mut sk_msg_md, bytes: u32) {
+        termination_check!(unsafe { ffi::bpf_msg_cork_bytes(msg.kptr, bytes) });
+    }
+
+    #[inline(always)]
+    fn convert_ctx(&self, ctx: *mut ()) -> sk_msg_md {
+        let kptr = unsafe { &mut *(ctx as *mut sk_msg_kern) };
+
+        let data_length = kptr.data_end as usize - kptr.data as usize;
+        let data_slice = unsafe {
+            slice::from_raw_parts_mut(kptr.data as *mut c_uchar, data_length)
+        };
+
+        sk_msg_md { data_slice, kptr }
+    }
+}
+
+impl rex_prog for sk_msg {
+    fn prog_run(&self, ctx: *mut ()) -> u32 {
+        let mut newctx = self.convert_ctx(ctx);
+        ((self.prog)(self, &mut newctx)).unwrap_or_else(|e| e) as u32
+    }
+}
@@ -0,0 +1,3 @@
+mod sk_reuseport_impl;
+
+pub use sk_reuseport_impl::*;
@@ -0,0 +1,129 @@
+use core::ffi::c_uchar;
+use core::intrinsics::unlikely;
+use core::slice;
+
+use crate::base_helper::termination_check;
+use crate::bindings::linux::kernel::{sk_buff, sk_reuseport_kern};
+use crate::bindings::uapi::linux::bpf::bpf_map_type;
+// expose the following constants to the user
+pub use crate::bindings::uapi::linux::bpf::{SK_DROP, SK_PASS};
+use crate::ffi;
+use crate::linux::errno::EINVAL;
+use crate::map::RexReuseportSockArray;
+use crate::prog_type::rex_prog;
+use crate::utils::*;
+
+pub struct sk_reuseport_md<'a> {
+    /// Packet data starting at the transport header
+    pub data_slice: &'a [c_uchar],
+    kptr: &'static mut sk_reuseport_kern,
+}
+
+// Define accessors of program-accessible fields
+impl sk_reuseport_md<'_> {
+    #[inline(always)]
+    fn skb(&self) -> &sk_buff {
+        unsafe { &*self.kptr.skb }
+    }
+
+    /// Total length of the packet
+    #[inline(always)]
+    pub fn len(&self) -> u32 {
+        self.skb().len
+    }
+
+    /// Ethernet protocol of the packet, e.g. `ETH_P_IP`
+    #[inline(always)]
+    pub fn eth_protocol(&self) -> u16be {
+        u16be(unsafe {
+            (self.skb().__bindgen_anon_4.__bindgen_anon_1)
+                .as_ref()
+                .protocol
+        })
+    }
+
+    /// IP protocol of the socket, i.e. `IPPROTO_TCP` or `IPPROTO_UDP`
+    #[inline(always)]
+    pub fn ip_protocol(&self) -> u16 {
+        unsafe { (*self.kptr.sk).sk_protocol }
+    }
+
+    /// Whether the sockets are bound to `INANY_ADDR`
+    #[inline(always)]
+    pub fn bind_inany(&self) -> bool {
+        self.kptr.bind_inany
+    }
+
+    /// Hash of the packet's 4-tuple
+    #[inline(always)]
+    pub fn hash(&self) -> u32 {
+        self.kptr.hash
+    }
+}
+
+/// prog_fn should have &Self as its first argument
+///
+/// The program returns `Ok(SK_PASS)` to use the socket selected by
+/// `bpf_sk_select_reuseport` (or to fall back to the kernel's selection if
+/// none is selected) and `Ok(SK_DROP)` to drop the packet. Returning an `Err`
+/// also drops the packet.
+#[repr(C)]
+pub struct sk_reuseport {
+    prog: fn(&Self, &mut sk_reuseport_md) -> Result,
+}
+
+impl sk_reuseport {
+    crate::base_helper::base_helper_defs!();
+
+    pub const unsafe fn new(
+        f: fn(&sk_reuseport, &mut sk_reuseport_md) -> Result,
+    ) -> sk_reuseport {
+        Self { prog: f }
+    }
+
+    /// Selects the socket at `key` in `map` from the reuseport group to
+    /// receive the packet
+    #[inline(always)]
+    pub fn bpf_sk_select_reuseport(
+        &self,
+        ctx: &mut sk_reuseport_md,
+        map: &'static RexReuseportSockArray,
+        key: u32,
+        flags: u32,
+    ) -> Result {
+        let map_kptr = unsafe { core::ptr::read_volatile(&map.kptr) };
+        if unlikely(map_kptr.is_null()) {
+            return Err(-(EINVAL as i32));
+        }
+
+        termination_check!(unsafe {
+            to_result!(ffi::sk_select_reuseport(
+                ctx.kptr,
+                map_kptr,
+                &key as *const u32 as *const (),
+                flags
+            ))
+        })
+    }
+
+    #[inline(always)]
+    fn convert_ctx(&self, ctx: *mut ()) -> sk_reuseport_md {
+        let kptr = unsafe { &mut *(ctx as *mut sk_reuseport_kern) };
+
+        let data = unsafe { (*kptr.skb).data };
+        let data_length = kptr.data_end as usize - data as usize;
+        let data_slice = unsafe {
+            slice::from_raw_parts(data as *const c_uchar, data_length)
+        };
+
+        sk_reuseport_md { data_slice, kptr }
+    }
+}
+
+impl rex_prog for sk_reuseport {
+    fn prog_run(&self, ctx: *mut ()) -> u32 {
+        let mut newctx = self.convert_ctx(ctx);
+        // drop the packet if error
+        ((self.prog)(self, &mut newctx)).unwrap_or(SK_DROP as i32) as u32
+    }
+}
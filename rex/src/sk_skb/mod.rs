@@ -0,0 +1,3 @@
+mod sk_skb_impl;
+
+pub use sk_skb_impl::*;
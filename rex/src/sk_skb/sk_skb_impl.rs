@@ -0,0 +1,107 @@
+use core::intrinsics::unlikely;
+
+use crate::base_helper::termination_check;
+use crate::bindings::uapi::linux::bpf::bpf_map_type;
+// expose the following constants to the user
+pub use crate::bindings::uapi::linux::bpf::{BPF_F_INGRESS, SK_DROP, SK_PASS};
+use crate::ffi;
+use crate::map::{RexSockHash, RexSockMap};
+use crate::prog_type::rex_prog;
+pub use crate::sched_cls::__sk_buff;
+use crate::utils::*;
+
+/// prog_fn should have &Self as its first argument
+///
+/// The same type is used for both stream parser and stream verdict programs:
+/// - A stream parser returns the length of the next message, `0` if more data
+///   is needed, or a negative errno.
+/// - A stream verdict returns `SK_PASS` or `SK_DROP`, redirecting the skb if
+///   one of the redirect helpers has been called.
+///
+/// Like other program types, an `Err` carries the return value as well. The
+/// skb data starts at the TCP payload.
+#[repr(C)]
+pub struct sk_skb {
+    prog: fn(&Self, &mut __sk_buff) -> Result,
+}
+
+impl sk_skb {
+    crate::base_helper::base_helper_defs!();
+
+    pub const unsafe fn new(
+        f: fn(&sk_skb, &mut __sk_buff) -> Result,
+    ) -> sk_skb {
+        Self { prog: f }
+    }
+
+    /// Redirects the skb to the socket at `key` in `map`, the direction is
+    /// egress unless `BPF_F_INGRESS` is set in `flags`.
+    ///
+    /// Returns `Ok(SK_PASS)` on success and `Err(SK_DROP)` on failure, which
+    /// can be returned from a stream verdict program as is.
+    #[inline(always)]
+    pub fn bpf_sk_redirect_map(
+        &self,
+        skb: &mut __sk_buff,
+        map: &'static RexSockMap,
+        key: u32,
+        flags: u64,
+    ) -> Result {
+        let map_kptr = unsafe { core::ptr::read_volatile(&map.kptr) };
+        if unlikely(map_kptr.is_null()) {
+            return Err(SK_DROP as i32);
+        }
+
+        let ret = termination_check!(unsafe {
+            ffi::bpf_sk_redirect_map(skb.kptr, map_kptr, key, flags)
+        });
+
+        if ret == SK_PASS as i64 {
+            Ok(SK_PASS as i32)
+        } else {
+            Err(SK_DROP as i32)
+        }
+    }
+
+    /// Same as [`Self::bpf_sk_redirect_map`] but for a sockhash
+    #[inline(always)]
+    pub fn bpf_sk_redirect_hash<K>(
+        &self,
+        skb: &mut __sk_buff,
+        map: &'static RexSockHash<K>,
+        key: &K,
+        flags: u64,
+    ) -> Result {
+        let map_kptr = unsafe { core::ptr::read_volatile(&map.kptr) };
+        if unlikely(map_kptr.is_null()) {
+            return Err(SK_DROP as i32);
+        }
+
+        let ret = termination_check!(unsafe {
+            ffi::bpf_sk_redirect_hash(
+                skb.kptr,
+                map_kptr,
+                key as *const K as *const (),
+                flags,
+            )
+        });
+
+        if ret == SK_PASS as i64 {
+            Ok(SK_PASS as i32)
+        } else {
+            Err(SK_DROP as i32)
+        }
+    }
+
+    #[inline(always)]
+    fn convert_ctx(&self, ctx: *mut ()) -> __sk_buff {
+        __sk_buff::from_ctx(ctx)
+    }
+}
+
+impl rex_prog for sk_skb {
+    fn prog_run(&self, ctx: *mut ()) -> u32 {
+        let mut newctx = self.convert_ctx(ctx);
+        ((self.prog)(self, &mut newctx)).unwrap_or_else(|e| e) as u32
+    }
+}
@@ -0,0 +1,3 @@
+mod sock_ops_impl;
+
+pub use sock_ops_impl::*;
@@ -0,0 +1,277 @@
+use core::mem;
+
+use crate::base_helper::termination_check;
+use crate::bindings::linux::kernel::{bpf_sock_ops_kern, sock};
+use crate::bindings::uapi::linux::bpf::{
+    bpf_map_type, BPF_SOCK_OPS_ACTIVE_ESTABLISHED_CB, BPF_SOCK_OPS_BASE_RTT,
+    BPF_SOCK_OPS_HDR_OPT_LEN_CB, BPF_SOCK_OPS_NEEDS_ECN,
+    BPF_SOCK_OPS_PARSE_HDR_OPT_CB, BPF_SOCK_OPS_PASSIVE_ESTABLISHED_CB,
+    BPF_SOCK_OPS_RETRANS_CB, BPF_SOCK_OPS_RTO_CB, BPF_SOCK_OPS_RTT_CB,
+    BPF_SOCK_OPS_RWND_INIT, BPF_SOCK_OPS_STATE_CB, BPF_SOCK_OPS_TCP_CONNECT_CB,
+    BPF_SOCK_OPS_TCP_LISTEN_CB, BPF_SOCK_OPS_TIMEOUT_INIT, BPF_SOCK_OPS_VOID,
+    BPF_SOCK_OPS_WRITE_HDR_OPT_CB,
+};
+// expose the following constants to the user
+pub use crate::bindings::uapi::linux::bpf::{
+    BPF_SOCK_OPS_ALL_CB_FLAGS, BPF_SOCK_OPS_RETRANS_CB_FLAG,
+    BPF_SOCK_OPS_RTO_CB_FLAG, BPF_SOCK_OPS_RTT_CB_FLAG,
+    BPF_SOCK_OPS_STATE_CB_FLAG,
+};
+use crate::ffi;
+use crate::prog_type::rex_prog;
+use crate::tcp_sock::TcpSock;
+use crate::utils::*;
+
+/// The point in the TCP stack where the program is called, i.e.
+/// `enum BPF_SOCK_OPS_*`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SockOp {
+    Void,
+    /// Reply with the SYN-RTO to use, -1 for the default
+    TimeoutInit,
+    /// Reply with the initial advertised window in packets, -1 for the
+    /// default
+    RwndInit,
+    TcpConnectCb,
+    ActiveEstablishedCb,
+    PassiveEstablishedCb,
+    /// Reply with 1 if the connection should use ECN
+    NeedsEcn,
+    BaseRtt,
+    RtoCb,
+    RetransCb,
+    StateCb,
+    TcpListenCb,
+    RttCb,
+    ParseHdrOptCb,
+    HdrOptLenCb,
+    WriteHdrOptCb,
+    Unknown(u32),
+}
+
+impl From<u32> for SockOp {
+    fn from(op: u32) -> Self {
+        match op {
+            BPF_SOCK_OPS_VOID => Self::Void,
+            BPF_SOCK_OPS_TIMEOUT_INIT => Self::TimeoutInit,
+            BPF_SOCK_OPS_RWND_INIT => Self::RwndInit,
+            BPF_SOCK_OPS_TCP_CONNECT_CB => Self::TcpConnectCb,
+            BPF_SOCK_OPS_ACTIVE_ESTABLISHED_CB => Self::ActiveEstablishedCb,
+            BPF_SOCK_OPS_PASSIVE_ESTABLISHED_CB => Self::PassiveEstablishedCb,
+            BPF_SOCK_OPS_NEEDS_ECN => Self::NeedsEcn,
+            BPF_SOCK_OPS_BASE_RTT => Self::BaseRtt,
+            BPF_SOCK_OPS_RTO_CB => Self::RtoCb,
+            BPF_SOCK_OPS_RETRANS_CB => Self::RetransCb,
+            BPF_SOCK_OPS_STATE_CB => Self::StateCb,
+            BPF_SOCK_OPS_TCP_LISTEN_CB => Self::TcpListenCb,
+            BPF_SOCK_OPS_RTT_CB => Self::RttCb,
+            BPF_SOCK_OPS_PARSE_HDR_OPT_CB => Self::ParseHdrOptCb,
+            BPF_SOCK_OPS_HDR_OPT_LEN_CB => Self::HdrOptLenCb,
+            BPF_SOCK_OPS_WRITE_HDR_OPT_CB => Self::WriteHdrOptCb,
+            op => Self::Unknown(op),
+        }
+    }
+}
+
+pub struct bpf_sock_ops {
+    kptr: &'static mut bpf_sock_ops_kern,
+}
+
+// Define accessors of program-accessible fields
+impl bpf_sock_ops {
+    #[inline(always)]
+    pub fn op(&self) -> SockOp {
+        SockOp::from(self.kptr.op as u32)
+    }
+
+    /// Arguments of the op, e.g. the old and new state for `StateCb`
+    #[inline(always)]
+    pub fn args(&self) -> [u32; 4] {
+        unsafe { self.kptr.__bindgen_anon_1.args }
+    }
+
+    /// Sets the reply to ops expecting one, e.g. `TimeoutInit`
+    #[inline(always)]
+    pub fn set_reply(&mut self, reply: u32) {
+        self.kptr.__bindgen_anon_1.reply = reply;
+    }
+
+    #[inline(always)]
+    pub fn is_fullsock(&self) -> bool {
+        self.kptr.is_fullsock != 0
+    }
+
+    #[inline(always)]
+    fn sk(&self) -> &sock {
+        unsafe { &*self.kptr.sk }
+    }
+
+    #[inline(always)]
+    pub fn family(&self) -> u16 {
+        self.sk().__sk_common.skc_family
+    }
+
+    /// TCP state of the socket, e.g. `BPF_TCP_ESTABLISHED`
+    #[inline(always)]
+    pub fn state(&self) -> u8 {
+        self.sk().__sk_common.skc_state
+    }
+
+    #[inline(always)]
+    pub fn remote_ip4(&self) -> u32be {
+        u32be(unsafe {
+            self.sk()
+                .__sk_common
+                .__bindgen_anon_1
+                .__bindgen_anon_1
+                .skc_daddr
+        })
+    }
+
+    #[inline(always)]
+    pub fn local_ip4(&self) -> u32be {
+        u32be(unsafe {
+            self.sk()
+                .__sk_common
+                .__bindgen_anon_1
+                .__bindgen_anon_1
+                .skc_rcv_saddr
+        })
+    }
+
+    #[inline(always)]
+    pub fn remote_ip6(&self) -> [u32; 4] {
+        unsafe { self.sk().__sk_common.skc_v6_daddr.in6_u.u6_addr32 }
+    }
+
+    #[inline(always)]
+    pub fn local_ip6(&self) -> [u32; 4] {
+        unsafe { self.sk().__sk_common.skc_v6_rcv_saddr.in6_u.u6_addr32 }
+    }
+
+    #[inline(always)]
+    pub fn remote_port(&self) -> u16be {
+        u16be(unsafe {
+            self.sk()
+                .__sk_common
+                .__bindgen_anon_3
+                .__bindgen_anon_1
+                .skc_dport
+        })
+    }
+
+    /// In host byte order, unlike [`Self::remote_port`]
+    #[inline(always)]
+    pub fn local_port(&self) -> u16 {
+        unsafe {
+            self.sk()
+                .__sk_common
+                .__bindgen_anon_3
+                .__bindgen_anon_1
+                .skc_num
+        }
+    }
+
+    /// The TCP socket, which gives access to the RTT and congestion control
+    /// fields. Only available for full sockets, i.e. not for request socks
+    #[inline(always)]
+    pub fn tcp_sock(&self) -> Option<&TcpSock> {
+        if self.is_fullsock() {
+            Some(TcpSock::from_sk(self.kptr.sk as u64))
+        } else {
+            None
+        }
+    }
+}
+
+/// prog_fn should have &Self as its first argument
+///
+/// Results of the op are passed through `bpf_sock_ops::set_reply`, the
+/// return value only tells whether the program succeeded.
+#[repr(C)]
+pub struct sock_ops {
+    prog: fn(&Self, &mut bpf_sock_ops) -> Result,
+}
+
+impl sock_ops {
+    crate::base_helper::base_helper_defs!();
+
+    pub const unsafe fn new(
+        f: fn(&sock_ops, &mut bpf_sock_ops) -> Result,
+    ) -> sock_ops {
+        Self { prog: f }
+    }
+
+    /// Sets the socket option `optname` at `level` to `val`, only a subset
+    /// of options is allowed by the kernel (e.g. `TCP_CONGESTION`,
+    /// `SO_SNDBUF`, `TCP_BPF_IW`)
+    #[inline(always)]
+    pub fn bpf_setsockopt<T: Copy + NoRef>(
+        &self,
+        ctx: &mut bpf_sock_ops,
+        level: i32,
+        optname: i32,
+        val: &T,
+    ) -> Result {
+        // The kernel does not write to optval on set, copy it anyway so that
+        // the signature can take a shared ref
+        let mut val = *val;
+        termination_check!(unsafe {
+            to_result!(ffi::bpf_sock_ops_setsockopt(
+                ctx.kptr,
+                level,
+                optname,
+                &mut val as *mut T as *mut (),
+                mem::size_of::<T>() as i32
+            ))
+        })
+    }
+
+    #[inline(always)]
+    pub fn bpf_getsockopt<T: Copy + NoRef>(
+        &self,
+        ctx: &mut bpf_sock_ops,
+        level: i32,
+        optname: i32,
+        val: &mut T,
+    ) -> Result {
+        termination_check!(unsafe {
+            to_result!(ffi::bpf_sock_ops_getsockopt(
+                ctx.kptr,
+                level,
+                optname,
+                val as *mut T as *mut (),
+                mem::size_of::<T>() as i32
+            ))
+        })
+    }
+
+    /// Enables the callbacks in `flags` (`BPF_SOCK_OPS_*_CB_FLAG`) for the
+    /// socket, e.g. `BPF_SOCK_OPS_RTT_CB_FLAG` to get `SockOp::RttCb`
+    #[inline(always)]
+    pub fn bpf_sock_ops_cb_flags_set(
+        &self,
+        ctx: &mut bpf_sock_ops,
+        flags: u32,
+    ) -> Result {
+        termination_check!(unsafe {
+            to_result!(ffi::bpf_sock_ops_cb_flags_set(ctx.kptr, flags as i32))
+        })
+    }
+
+    #[inline(always)]
+    fn convert_ctx(&self, ctx: *mut ()) -> bpf_sock_ops {
+        let kptr = unsafe { &mut *(ctx as *mut bpf_sock_ops_kern) };
+        bpf_sock_ops { kptr }
+    }
+}
+
+impl rex_prog for sock_ops {
+    fn prog_run(&self, ctx: *mut ()) -> u32 {
+        let mut newctx = self.convert_ctx(ctx);
+        match (self.prog)(self, &mut newctx) {
+            Ok(_) => 1,
+            Err(_) => 0,
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use core::mem::size_of_val;
+
+use crate::base_helper::termination_check;
+use crate::bindings::linux::kernel::sock;
+use crate::bindings::uapi::linux::bpf::bpf_sock_tuple;
+// expose the following constants to the user
+pub use crate::bindings::uapi::linux::bpf::{
+    BPF_TCP_CLOSE, BPF_TCP_ESTABLISHED, BPF_TCP_LISTEN, BPF_TCP_SYN_RECV,
+    BPF_TCP_TIME_WAIT,
+};
+use crate::ffi;
+use crate::fib::addr_to_words;
+use crate::iter::SockCommon;
+use crate::panic::CleanupEntries;
+use crate::utils::{u16be, u32be};
+
+// BPF_F_CURRENT_NETNS (-1) as the netns_id of the skb and XDP lookup helpers,
+// which take a u64 and a u32 respectively
+pub(crate) const CURRENT_NETNS_SKB: u64 = u64::MAX;
+pub(crate) const CURRENT_NETNS_XDP: u32 = u32::MAX;
+
+/// The 4-tuple of a socket lookup, from the point of view of the socket,
+/// i.e. `saddr`/`sport` are the remote end and `daddr`/`dport` are the
+/// local end for a packet received by the host
+#[derive(Debug, Copy, Clone)]
+pub enum SockTuple {
+    Ipv4 {
+        saddr: u32be,
+        daddr: u32be,
+        sport: u16be,
+        dport: u16be,
+    },
+    Ipv6 {
+        saddr: [u8; 16],
+        daddr: [u8; 16],
+        sport: u16be,
+        dport: u16be,
+    },
+}
+
+impl SockTuple {
+    /// Converts the tuple to its kernel representation and the size to pass
+    /// to the lookup helpers, which selects the address family
+    pub(crate) fn to_raw(self) -> (bpf_sock_tuple, u32) {
+        let mut raw = bpf_sock_tuple::default();
+
+        // Only one member of the union is ever written
+        let size = unsafe {
+            match self {
+                SockTuple::Ipv4 {
+                    saddr,
+                    daddr,
+                    sport,
+                    dport,
+                } => {
+                    let v4 = &mut raw.__bindgen_anon_1.ipv4;
+                    v4.saddr = saddr.be_bits();
+                    v4.daddr = daddr.be_bits();
+                    v4.sport = sport.be_bits();
+                    v4.dport = dport.be_bits();
+                    size_of_val(v4)
+                }
+                SockTuple::Ipv6 {
+                    saddr,
+                    daddr,
+                    sport,
+                    dport,
+                } => {
+                    let v6 = &mut raw.__bindgen_anon_1.ipv6;
+                    v6.saddr = addr_to_words(&saddr);
+                    v6.daddr = addr_to_words(&daddr);
+                    v6.sport = sport.be_bits();
+                    v6.dport = dport.be_bits();
+                    size_of_val(v6)
+                }
+            }
+        };
+
+        (raw, size as u32)
+    }
+}
+
+/// An RAII implementation of a socket reference acquired by a socket lookup
+/// helper (e.g. `bpf_sk_lookup_tcp`). When this structure is dropped (falls
+/// out of scope), the reference is released with `bpf_sk_release`.
+#[must_use = "if unused the socket will immediately be released"]
+#[clippy::has_significant_drop]
+pub struct rex_sock_guard {
+    sk: *mut sock,
+    cleanup_idx: usize,
+}
+
+impl rex_sock_guard {
+    /// Runs a lookup helper with the raw tuple and its size, and wraps the
+    /// returned socket if there is one
+    #[inline(always)]
+    pub(crate) fn lookup<F>(tuple: &SockTuple, lookup_fn: F) -> Option<Self>
+    where
+        F: FnOnce(*mut bpf_sock_tuple, u32) -> *mut sock,
+    {
+        let (mut raw, size) = tuple.to_raw();
+        Self::acquire(|| lookup_fn(&mut raw, size))
+    }
+
+    /// Runs a helper returning a referenced socket (e.g. a sockmap lookup)
+    /// and wraps the socket if there is one
+    #[inline(always)]
+    pub(crate) fn acquire<F>(acquire_fn: F) -> Option<Self>
+    where
+        F: FnOnce() -> *mut sock,
+    {
+        termination_check!({
+            // Put it before the helper so if it panics we will not be holding
+            // a reference without a valid cleanup entry for it, the socket is
+            // filled in within the same check
+            let cleanup_idx = CleanupEntries::register_cleanup(
+                Self::panic_cleanup,
+                core::ptr::null_mut(),
+            );
+
+            let sk = acquire_fn();
+
+            if sk.is_null() {
+                CleanupEntries::deregister_cleanup(cleanup_idx);
+                None
+            } else {
+                CleanupEntries::set_cleanup_arg(cleanup_idx, sk as *mut ());
+                Some(Self { sk, cleanup_idx })
+            }
+        })
+    }
+
+    /// The raw socket, only valid as long as the guard is alive
+    #[inline(always)]
+    pub(crate) fn as_ptr(&self) -> *mut sock {
+        self.sk
+    }
+
+    /// Function that releases the socket, used by cleanup list
+    pub(crate) unsafe fn panic_cleanup(sk: *mut ()) {
+        if !sk.is_null() {
+            unsafe {
+                ffi::bpf_sk_release(sk as *mut sock);
+            }
+        }
+    }
+
+    /// The fields shared by all kinds of sockets, e.g. the state to check
+    /// for a listening socket
+    #[inline(always)]
+    pub fn sk_common(&self) -> SockCommon<'_> {
+        SockCommon::new(unsafe { &(*self.sk).__sk_common })
+    }
+}
+
+impl Drop for rex_sock_guard {
+    /// Release the socket when the guard is out-of-scope
+    fn drop(&mut self) {
+        termination_check!({
+            // Put it before release so if it panics we will not release twice
+            CleanupEntries::deregister_cleanup(self.cleanup_idx);
+
+            unsafe { ffi::bpf_sk_release(self.sk) };
+        })
+    }
+}
+
+/// Unimplement Send and Sync
+impl !Send for rex_sock_guard {}
+impl !Sync for rex_sock_guard {}
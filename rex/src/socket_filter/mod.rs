@@ -0,0 +1,3 @@
+mod socket_filter_impl;
+
+pub use socket_filter_impl::*;
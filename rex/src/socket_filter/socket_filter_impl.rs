@@ -0,0 +1,43 @@
+use crate::bindings::uapi::linux::bpf::bpf_map_type;
+use crate::prog_type::rex_prog;
+pub use crate::sched_cls::__sk_buff;
+use crate::sched_cls::skb_net_header_defs;
+use crate::utils::*;
+
+/// prog_fn should have &Self as its first argument
+///
+/// The program returns the number of bytes of the packet to keep, e.g.
+/// `Ok(skb.len() as i32)` keeps the whole packet and `Ok(0)` drops it.
+/// Returning an `Err` also drops the packet.
+#[repr(C)]
+pub struct socket_filter {
+    prog: fn(&Self, &mut __sk_buff) -> Result,
+}
+
+impl socket_filter {
+    crate::base_helper::base_helper_defs!();
+
+    // The skb data starts at the MAC header on packet sockets but at the
+    // transport header on e.g. UDP sockets, so the headers are located with
+    // the header offsets of the skb
+    skb_net_header_defs!(__sk_buff, |skb: &__sk_buff| &*skb.kptr);
+
+    pub const unsafe fn new(
+        f: fn(&socket_filter, &mut __sk_buff) -> Result,
+    ) -> socket_filter {
+        Self { prog: f }
+    }
+
+    #[inline(always)]
+    fn convert_ctx(&self, ctx: *mut ()) -> __sk_buff {
+        __sk_buff::from_ctx(ctx)
+    }
+}
+
+impl rex_prog for socket_filter {
+    fn prog_run(&self, ctx: *mut ()) -> u32 {
+        let mut newctx = self.convert_ctx(ctx);
+        // drop the packet if error
+        ((self.prog)(self, &mut newctx)).unwrap_or(0) as u32
+    }
+}
@@ -0,0 +1,3 @@
+mod tcp_congestion_ops_impl;
+
+pub use tcp_congestion_ops_impl::*;
@@ -0,0 +1,256 @@
+use core::marker::PhantomData;
+use core::mem;
+
+use crate::base_helper::termination_check;
+use crate::bindings::linux::kernel::tcp_congestion_ops as tcp_congestion_ops_kern;
+use crate::bindings::uapi::linux::bpf::bpf_map_type;
+use crate::ffi;
+use crate::prog_type::rex_prog;
+pub use crate::tcp_sock::TcpSock;
+
+/// `TCP_CA_NAME_MAX`
+pub const TCP_CA_NAME_MAX: usize = 16;
+
+/// `struct ack_sample`
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct AckSample {
+    pub pkts_acked: u32,
+    pub rtt_us: i32,
+    pub in_flight: u32,
+}
+
+/// A TCP congestion control algorithm.
+///
+/// `ssthresh`, `cong_avoid` and `undo_cwnd` are mandatory, the rest of the
+/// callbacks are optional and only attached if implemented. Use
+/// `#[rex_tcp_congestion_ops(name = "...")]` on the impl block to generate
+/// the programs and the struct_ops map.
+pub trait TcpCongestionOps: Sized + 'static {
+    /// Returns the slow start threshold after a loss
+    fn ssthresh(prog: &tcp_congestion_ops<Self>, tp: &mut TcpSock) -> u32;
+
+    /// Grows the congestion window upon `acked` packets being acked
+    fn cong_avoid(
+        prog: &tcp_congestion_ops<Self>,
+        tp: &mut TcpSock,
+        ack: u32,
+        acked: u32,
+    );
+
+    /// Returns the congestion window to use after a false loss detection
+    fn undo_cwnd(prog: &tcp_congestion_ops<Self>, tp: &mut TcpSock) -> u32;
+
+    fn init(_prog: &tcp_congestion_ops<Self>, _tp: &mut TcpSock) {}
+
+    fn release(_prog: &tcp_congestion_ops<Self>, _tp: &mut TcpSock) {}
+
+    fn set_state(
+        _prog: &tcp_congestion_ops<Self>,
+        _tp: &mut TcpSock,
+        _new_state: u8,
+    ) {
+    }
+
+    fn cwnd_event(
+        _prog: &tcp_congestion_ops<Self>,
+        _tp: &mut TcpSock,
+        _ev: u32,
+    ) {
+    }
+
+    fn in_ack_event(
+        _prog: &tcp_congestion_ops<Self>,
+        _tp: &mut TcpSock,
+        _flags: u32,
+    ) {
+    }
+
+    fn pkts_acked(
+        _prog: &tcp_congestion_ops<Self>,
+        _tp: &mut TcpSock,
+        _sample: &AckSample,
+    ) {
+    }
+
+    fn min_tso_segs(
+        _prog: &tcp_congestion_ops<Self>,
+        _tp: &mut TcpSock,
+    ) -> u32 {
+        0
+    }
+
+    fn sndbuf_expand(
+        _prog: &tcp_congestion_ops<Self>,
+        _tp: &mut TcpSock,
+    ) -> u32 {
+        0
+    }
+}
+
+/// Callbacks of `struct tcp_congestion_ops` that can be implemented in Rex
+#[derive(Debug, Copy, Clone)]
+pub enum TcpCongestionOpsCallback {
+    Ssthresh,
+    CongAvoid,
+    SetState,
+    CwndEvent,
+    InAckEvent,
+    PktsAcked,
+    MinTsoSegs,
+    UndoCwnd,
+    SndbufExpand,
+    Init,
+    Release,
+}
+
+/// One callback of a TCP congestion control algorithm, the callback
+/// implementation comes from `T`
+#[repr(C)]
+pub struct tcp_congestion_ops<T: TcpCongestionOps> {
+    cb: TcpCongestionOpsCallback,
+    ops: PhantomData<T>,
+}
+
+impl<T: TcpCongestionOps> tcp_congestion_ops<T> {
+    crate::base_helper::base_helper_defs!();
+
+    pub const unsafe fn new(
+        cb: TcpCongestionOpsCallback,
+    ) -> tcp_congestion_ops<T> {
+        Self {
+            cb,
+            ops: PhantomData,
+        }
+    }
+
+    /// Slow start, returns the number of acked packets not consumed
+    #[inline(always)]
+    pub fn tcp_slow_start(&self, tp: &mut TcpSock, acked: u32) -> u32 {
+        termination_check!(unsafe { ffi::tcp_slow_start(&mut tp.tp, acked) })
+    }
+
+    /// Additive increase of the congestion window, by 1 every `w` acked
+    /// packets
+    #[inline(always)]
+    pub fn tcp_cong_avoid_ai(&self, tp: &mut TcpSock, w: u32, acked: u32) {
+        termination_check!(unsafe {
+            ffi::tcp_cong_avoid_ai(&mut tp.tp, w, acked)
+        })
+    }
+
+    #[inline(always)]
+    pub fn tcp_reno_ssthresh(&self, tp: &mut TcpSock) -> u32 {
+        termination_check!(unsafe { ffi::tcp_reno_ssthresh(tp.as_sk()) })
+    }
+
+    #[inline(always)]
+    pub fn tcp_reno_cong_avoid(&self, tp: &mut TcpSock, ack: u32, acked: u32) {
+        termination_check!(unsafe {
+            ffi::tcp_reno_cong_avoid(tp.as_sk(), ack, acked)
+        })
+    }
+
+    #[inline(always)]
+    pub fn tcp_reno_undo_cwnd(&self, tp: &mut TcpSock) -> u32 {
+        termination_check!(unsafe { ffi::tcp_reno_undo_cwnd(tp.as_sk()) })
+    }
+
+    fn convert_ctx(&self, ctx: *mut ()) -> &'static [u64; 4] {
+        // The context is an array holding the arguments of the callback,
+        // the callbacks supported take at most 3 arguments
+        unsafe { &*(ctx as *const [u64; 4]) }
+    }
+}
+
+impl<T: TcpCongestionOps> rex_prog for tcp_congestion_ops<T> {
+    fn prog_run(&self, ctx: *mut ()) -> u32 {
+        use TcpCongestionOpsCallback::*;
+
+        let args = self.convert_ctx(ctx);
+        let tp = TcpSock::from_sk(args[0]);
+
+        match self.cb {
+            Ssthresh => T::ssthresh(self, tp),
+            CongAvoid => {
+                T::cong_avoid(self, tp, args[1] as u32, args[2] as u32);
+                0
+            }
+            SetState => {
+                T::set_state(self, tp, args[1] as u8);
+                0
+            }
+            CwndEvent => {
+                T::cwnd_event(self, tp, args[1] as u32);
+                0
+            }
+            InAckEvent => {
+                T::in_ack_event(self, tp, args[1] as u32);
+                0
+            }
+            PktsAcked => {
+                let sample = unsafe { &*(args[1] as *const AckSample) };
+                T::pkts_acked(self, tp, sample);
+                0
+            }
+            MinTsoSegs => T::min_tso_segs(self, tp),
+            UndoCwnd => T::undo_cwnd(self, tp),
+            SndbufExpand => T::sndbuf_expand(self, tp),
+            Init => {
+                T::init(self, tp);
+                0
+            }
+            Release => {
+                T::release(self, tp);
+                0
+            }
+        }
+    }
+}
+
+/// Entry point of a struct_ops callback
+pub type RexStructOpsEntry = extern "C" fn(*mut ()) -> u32;
+
+/// Rex equivalent of `struct tcp_congestion_ops` placed in the
+/// `.struct_ops` section. It follows the layout of the kernel struct, with
+/// each callback pointing to the entry point of its program, and is
+/// registered by the loader as a struct_ops map.
+#[repr(C, align(64))]
+pub struct RexTcpCongestionOps {
+    pub ssthresh: Option<RexStructOpsEntry>,
+    pub cong_avoid: Option<RexStructOpsEntry>,
+    pub set_state: Option<RexStructOpsEntry>,
+    pub cwnd_event: Option<RexStructOpsEntry>,
+    pub in_ack_event: Option<RexStructOpsEntry>,
+    pub pkts_acked: Option<RexStructOpsEntry>,
+    pub min_tso_segs: Option<RexStructOpsEntry>,
+    /// Not supported yet, must be `None`
+    pub cong_control: Option<RexStructOpsEntry>,
+    pub undo_cwnd: Option<RexStructOpsEntry>,
+    pub sndbuf_expand: Option<RexStructOpsEntry>,
+    /// Not supported yet, must be `None`
+    pub get_info: Option<RexStructOpsEntry>,
+    pub name: [u8; TCP_CA_NAME_MAX],
+    /// `owner`, `list` and `key` are filled in by the kernel, must be zero
+    pub owner: usize,
+    pub list: [usize; 2],
+    pub key: u32,
+    pub flags: u32,
+    pub init: Option<RexStructOpsEntry>,
+    pub release: Option<RexStructOpsEntry>,
+}
+
+const _: () = {
+    use core::mem::offset_of;
+    type Kern = tcp_congestion_ops_kern;
+
+    assert!(mem::size_of::<RexTcpCongestionOps>() == mem::size_of::<Kern>());
+    assert!(
+        offset_of!(RexTcpCongestionOps, get_info) == offset_of!(Kern, get_info)
+    );
+    assert!(offset_of!(RexTcpCongestionOps, name) == offset_of!(Kern, name));
+    assert!(offset_of!(RexTcpCongestionOps, flags) == offset_of!(Kern, flags));
+    assert!(
+        offset_of!(RexTcpCongestionOps, release) == offset_of!(Kern, release)
+    );
+};
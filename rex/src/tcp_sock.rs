@@ -0,0 +1,159 @@
+use core::mem;
+
+use crate::bindings::linux::kernel::{inet_connection_sock, sock, tcp_sock};
+use crate::utils::NoRef;
+
+/// Size of `icsk_ca_priv` in `struct inet_connection_sock`
+const ICSK_CA_PRIV_SIZE: usize = {
+    const fn size_of_field<T, F>(_: fn(&T) -> &F) -> usize {
+        mem::size_of::<F>()
+    }
+    size_of_field(|icsk: &inet_connection_sock| &icsk.icsk_ca_priv)
+};
+
+/// Wrapper of the kernel `struct tcp_sock`, setters are only reachable where
+/// the socket is handed out mutably (e.g. congestion control callbacks)
+#[repr(transparent)]
+pub struct TcpSock {
+    pub(crate) tp: tcp_sock,
+}
+
+impl TcpSock {
+    #[inline(always)]
+    pub(crate) fn from_sk(sk: u64) -> &'static mut Self {
+        // struct tcp_sock starts with struct sock (through inet_sock and
+        // inet_connection_sock), the caller guarantees the sock is a
+        // tcp_sock
+        unsafe { &mut *(sk as *mut Self) }
+    }
+
+    #[inline(always)]
+    pub fn snd_cwnd(&self) -> u32 {
+        self.tp.snd_cwnd
+    }
+
+    #[inline(always)]
+    pub fn set_snd_cwnd(&mut self, val: u32) {
+        self.tp.snd_cwnd = val;
+    }
+
+    #[inline(always)]
+    pub fn snd_cwnd_cnt(&self) -> u32 {
+        self.tp.snd_cwnd_cnt
+    }
+
+    #[inline(always)]
+    pub fn set_snd_cwnd_cnt(&mut self, val: u32) {
+        self.tp.snd_cwnd_cnt = val;
+    }
+
+    #[inline(always)]
+    pub fn snd_cwnd_clamp(&self) -> u32 {
+        self.tp.snd_cwnd_clamp
+    }
+
+    #[inline(always)]
+    pub fn snd_ssthresh(&self) -> u32 {
+        self.tp.snd_ssthresh
+    }
+
+    #[inline(always)]
+    pub fn set_snd_ssthresh(&mut self, val: u32) {
+        self.tp.snd_ssthresh = val;
+    }
+
+    #[inline(always)]
+    pub fn prior_cwnd(&self) -> u32 {
+        self.tp.prior_cwnd
+    }
+
+    /// Smoothed RTT in microseconds, shifted left by 3
+    #[inline(always)]
+    pub fn srtt_us(&self) -> u32 {
+        self.tp.srtt_us
+    }
+
+    #[inline(always)]
+    pub fn mss_cache(&self) -> u32 {
+        self.tp.mss_cache
+    }
+
+    #[inline(always)]
+    pub fn packets_out(&self) -> u32 {
+        self.tp.packets_out
+    }
+
+    /// RTT mean deviation in microseconds, shifted left by 2
+    #[inline(always)]
+    pub fn mdev_us(&self) -> u32 {
+        self.tp.mdev_us
+    }
+
+    /// Minimum RTT in microseconds over the recent window
+    #[inline(always)]
+    pub fn rtt_min_us(&self) -> u32 {
+        self.tp.rtt_min.s[0].v
+    }
+
+    #[inline(always)]
+    pub fn total_retrans(&self) -> u32 {
+        self.tp.total_retrans
+    }
+
+    #[inline(always)]
+    pub fn segs_in(&self) -> u32 {
+        self.tp.segs_in
+    }
+
+    #[inline(always)]
+    pub fn segs_out(&self) -> u32 {
+        self.tp.segs_out
+    }
+
+    #[inline(always)]
+    pub fn bytes_acked(&self) -> u64 {
+        self.tp.bytes_acked
+    }
+
+    #[inline(always)]
+    pub fn bytes_received(&self) -> u64 {
+        self.tp.bytes_received
+    }
+
+    #[inline(always)]
+    pub fn snd_una(&self) -> u32 {
+        self.tp.snd_una
+    }
+
+    #[inline(always)]
+    pub fn snd_nxt(&self) -> u32 {
+        self.tp.snd_nxt
+    }
+
+    #[inline(always)]
+    pub fn rcv_nxt(&self) -> u32 {
+        self.tp.rcv_nxt
+    }
+
+    #[inline(always)]
+    pub fn in_slow_start(&self) -> bool {
+        self.tp.snd_cwnd < self.tp.snd_ssthresh
+    }
+
+    /// Per-socket private area of the congestion control algorithm
+    /// (`icsk_ca_priv`), zero-initialized when the algorithm is assigned
+    #[inline(always)]
+    pub fn ca_priv_mut<T: Copy + NoRef>(&mut self) -> &mut T {
+        const {
+            assert!(mem::size_of::<T>() <= ICSK_CA_PRIV_SIZE);
+            assert!(mem::align_of::<T>() <= mem::align_of::<u64>());
+        }
+        let ca_priv = &mut self.tp.inet_conn.icsk_ca_priv;
+        unsafe { &mut *(ca_priv.as_mut_ptr() as *mut T) }
+    }
+
+    #[inline(always)]
+    pub(crate) fn as_sk(&mut self) -> *mut sock {
+        &mut self.tp as *mut tcp_sock as *mut sock
+    }
+}
@@ -0,0 +1,3 @@
+mod usdt_impl;
+
+pub use usdt_impl::*;
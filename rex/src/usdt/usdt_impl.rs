@@ -0,0 +1,194 @@
+use crate::base_helper::bpf_probe_read_user;
+use crate::map::RexArrayMap;
+use crate::prog_type::rex_prog;
+use crate::pt_regs::PtRegs;
+use crate::task_struct::TaskStruct;
+use crate::{ffi, Result};
+
+/// Maximum number of arguments of a probe, must match `usdt::max_args` in
+/// librex
+pub const USDT_MAX_ARGS: usize = 12;
+
+/// Maximum number of distinct argument specs across the sites of a probe,
+/// must match `usdt::max_specs` in librex
+pub const USDT_MAX_SPECS: u32 = 256;
+
+// Where the value of a USDT argument lives, must match `usdt::arg_loc` in
+// librex/lib/usdt.h
+const USDT_ARG_CONST: u8 = 0;
+const USDT_ARG_REG: u8 = 1;
+const USDT_ARG_REG_DEREF: u8 = 2;
+
+// The register holding a USDT argument, must match `usdt::x86_reg` in
+// librex/lib/usdt.h
+const USDT_REG_RAX: u8 = 0;
+const USDT_REG_RBX: u8 = 1;
+const USDT_REG_RCX: u8 = 2;
+const USDT_REG_RDX: u8 = 3;
+const USDT_REG_RSI: u8 = 4;
+const USDT_REG_RDI: u8 = 5;
+const USDT_REG_RBP: u8 = 6;
+const USDT_REG_RSP: u8 = 7;
+const USDT_REG_RIP: u8 = 8;
+const USDT_REG_R8: u8 = 9;
+const USDT_REG_R9: u8 = 10;
+const USDT_REG_R10: u8 = 11;
+const USDT_REG_R11: u8 = 12;
+const USDT_REG_R12: u8 = 13;
+const USDT_REG_R13: u8 = 14;
+const USDT_REG_R14: u8 = 15;
+const USDT_REG_R15: u8 = 16;
+
+/// An argument spec parsed by librex from the `.note.stapsdt` section of the
+/// target binary, e.g. `-4@%edi` or `8@-8(%rbp)`, same layout as
+/// `usdt::arg_spec` in librex
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct UsdtArgSpec {
+    /// The constant or the offset from the register
+    val_off: i64,
+    loc: u8,
+    /// One of the `USDT_REG_*` constants
+    reg: u8,
+    /// 8 for the high byte registers, e.g. `%ah`
+    reg_shift: u8,
+    size: u8,
+    signed: u8,
+}
+
+/// The argument specs of a probe site, stored by librex in the spec map of
+/// the program at the index passed to the site as the BPF cookie
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct UsdtSpec {
+    args: [UsdtArgSpec; USDT_MAX_ARGS],
+    arg_cnt: u32,
+}
+
+/// Integer types that a USDT argument can be fetched as
+pub trait UsdtArg: Copy {
+    fn from_raw(val: u64) -> Self;
+}
+
+macro_rules! impl_usdt_arg {
+    ($($t:ty)*) => {
+        $(
+            impl UsdtArg for $t {
+                #[inline(always)]
+                fn from_raw(val: u64) -> Self {
+                    val as $t
+                }
+            }
+        )*
+    };
+}
+
+impl_usdt_arg!(u8 u16 u32 u64 usize i8 i16 i32 i64 isize);
+
+/// prog_fn should have &Self as its first argument
+#[repr(C)]
+pub struct usdt {
+    prog: fn(&Self, &mut PtRegs) -> Result,
+    specs: &'static RexArrayMap<UsdtSpec>,
+}
+
+impl usdt {
+    crate::base_helper::base_helper_defs!();
+
+    pub const unsafe fn new(
+        f: fn(&usdt, &mut PtRegs) -> Result,
+        specs: &'static RexArrayMap<UsdtSpec>,
+    ) -> usdt {
+        Self { prog: f, specs }
+    }
+
+    // See kprobe::convert_ctx
+    fn convert_ctx(&self, ctx: *mut ()) -> &'static mut PtRegs {
+        unsafe { &mut *(ctx as *mut PtRegs) }
+    }
+
+    pub fn bpf_get_current_task(&self) -> Option<TaskStruct> {
+        TaskStruct::get_current_task()
+    }
+
+    // The specs of the site that fired, librex sets the BPF cookie of each
+    // site to the index of its specs
+    fn spec(&self, regs: &PtRegs) -> Option<UsdtSpec> {
+        let id = unsafe {
+            ffi::bpf_get_attach_cookie_trace(regs as *const PtRegs as *mut ())
+        } as u32;
+        self.specs.get_mut(&id).map(|spec| *spec)
+    }
+
+    /// Number of arguments of the probe
+    pub fn arg_count(&self, regs: &PtRegs) -> usize {
+        self.spec(regs).map_or(0, |spec| spec.arg_cnt as usize)
+    }
+
+    /// Fetches the `n`-th argument of the probe, truncated to `T`.
+    ///
+    /// Returns `None` if `n` is out of range or if the argument cannot be
+    /// read from user memory.
+    pub fn arg<T: UsdtArg>(&self, regs: &PtRegs, n: usize) -> Option<T> {
+        let spec = self.spec(regs)?;
+        if n >= spec.arg_cnt as usize {
+            return None;
+        }
+        let arg = spec.args.get(n)?;
+
+        let val = match arg.loc {
+            USDT_ARG_CONST => arg.val_off as u64,
+            USDT_ARG_REG => Self::read_reg(regs, arg.reg)? >> arg.reg_shift,
+            USDT_ARG_REG_DEREF => {
+                let addr = Self::read_reg(regs, arg.reg)?
+                    .wrapping_add(arg.val_off as u64);
+                let mut val = 0u64;
+                bpf_probe_read_user(&mut val, addr as *const ()).ok()?;
+                val
+            }
+            _ => return None,
+        };
+
+        // Only the low `size` bytes are meaningful, shift them to the top
+        // and back to sign- or zero-extend the value (same as libbpf)
+        let shift = 64 - (arg.size as u32 * 8).clamp(8, 64);
+        let val = if arg.signed != 0 {
+            (((val << shift) as i64) >> shift) as u64
+        } else {
+            (val << shift) >> shift
+        };
+
+        Some(T::from_raw(val))
+    }
+
+    fn read_reg(regs: &PtRegs, reg: u8) -> Option<u64> {
+        let val = match reg {
+            USDT_REG_RAX => regs.rax(),
+            USDT_REG_RBX => regs.rbx(),
+            USDT_REG_RCX => regs.rcx(),
+            USDT_REG_RDX => regs.rdx(),
+            USDT_REG_RSI => regs.rsi(),
+            USDT_REG_RDI => regs.rdi(),
+            USDT_REG_RBP => regs.rbp(),
+            USDT_REG_RSP => regs.rsp(),
+            USDT_REG_RIP => regs.rip(),
+            USDT_REG_R8 => regs.r8(),
+            USDT_REG_R9 => regs.r9(),
+            USDT_REG_R10 => regs.r10(),
+            USDT_REG_R11 => regs.r11(),
+            USDT_REG_R12 => regs.r12(),
+            USDT_REG_R13 => regs.r13(),
+            USDT_REG_R14 => regs.r14(),
+            USDT_REG_R15 => regs.r15(),
+            _ => return None,
+        };
+        Some(val)
+    }
+}
+
+impl rex_prog for usdt {
+    fn prog_run(&self, ctx: *mut ()) -> u32 {
+        let newctx = self.convert_ctx(ctx);
+        ((self.prog)(self, newctx)).unwrap_or_else(|e| e) as u32
+    }
+}
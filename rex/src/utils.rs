@@ -6,17 +6,122 @@ use crate::bindings::uapi::linux::bpf::{BPF_F_CURRENT_CPU, BPF_F_INDEX_MASK};
 use crate::map::RexPerfEventArray;
 use crate::prog_type::rex_prog;
 
-#[repr(transparent)]
-#[derive(Copy, Clone)]
-pub struct u16be(pub(crate) u16);
-
-impl From<u16be> for u16 {
-    // Required method
-    fn from(value: u16be) -> Self {
-        u16::from_be(value.0)
-    }
+/// Defines a network-byte-order (big-endian) integer type `$be` backed by
+/// the host integer type `$host`. Comparisons are done on the host-order
+/// values, so e.g. `udp.dest() == 53` does the right thing.
+macro_rules! def_be_int {
+    ($(#[$attr:meta])* $be:ident, $host:ty) => {
+        $(#[$attr])*
+        #[repr(transparent)]
+        #[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+        pub struct $be(pub(crate) $host);
+
+        impl $be {
+            /// Converts a host-order value to network order
+            #[inline(always)]
+            pub const fn from_host(value: $host) -> Self {
+                Self(value.to_be())
+            }
+
+            /// Wraps bits that are already in network order, e.g. read
+            /// directly from a packet or a map shared with the userspace
+            #[inline(always)]
+            pub const fn from_be_bits(bits: $host) -> Self {
+                Self(bits)
+            }
+
+            /// The value in host order
+            #[inline(always)]
+            pub const fn to_host(self) -> $host {
+                <$host>::from_be(self.0)
+            }
+
+            /// The underlying bits in network order
+            #[inline(always)]
+            pub const fn be_bits(self) -> $host {
+                self.0
+            }
+        }
+
+        impl From<$be> for $host {
+            #[inline(always)]
+            fn from(value: $be) -> Self {
+                value.to_host()
+            }
+        }
+
+        impl From<$host> for $be {
+            #[inline(always)]
+            fn from(value: $host) -> Self {
+                Self::from_host(value)
+            }
+        }
+
+        impl PartialEq<$host> for $be {
+            #[inline(always)]
+            fn eq(&self, other: &$host) -> bool {
+                self.to_host() == *other
+            }
+        }
+
+        impl PartialEq<$be> for $host {
+            #[inline(always)]
+            fn eq(&self, other: &$be) -> bool {
+                *self == other.to_host()
+            }
+        }
+
+        impl PartialOrd for $be {
+            #[inline(always)]
+            fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $be {
+            #[inline(always)]
+            fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+                self.to_host().cmp(&other.to_host())
+            }
+        }
+
+        impl PartialOrd<$host> for $be {
+            #[inline(always)]
+            fn partial_cmp(&self, other: &$host) -> Option<core::cmp::Ordering> {
+                self.to_host().partial_cmp(other)
+            }
+        }
+
+        impl core::fmt::Debug for $be {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                core::fmt::Debug::fmt(&self.to_host(), f)
+            }
+        }
+
+        impl core::fmt::Display for $be {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                core::fmt::Display::fmt(&self.to_host(), f)
+            }
+        }
+    };
 }
 
+def_be_int!(
+    /// A `__be16`, e.g. a port or an EtherType
+    u16be,
+    u16
+);
+def_be_int!(
+    /// A `__be32`, e.g. an IPv4 address
+    u32be,
+    u32
+);
+def_be_int!(
+    /// A `__be64`
+    u64be,
+    u64
+);
+
 /// A specialized Result for typical int return value in the kernel
 ///
 /// To be used as the return type for functions that may fail.
@@ -227,6 +332,21 @@ where
     }
 }
 
+/// Converts the bytes at `off` in `slice` into a `&T` like
+/// [`convert_slice_to_struct`], returning `None` instead of panicking if
+/// they do not fit in the slice.
+#[inline]
+pub(crate) fn convert_slice_at_to_struct<T>(
+    slice: &[c_uchar],
+    off: usize,
+) -> Option<Aligned<'_, T>>
+where
+    T: Copy + NoRef,
+{
+    let end = off.checked_add(mem::size_of::<T>())?;
+    Some(convert_slice_to_struct::<T>(slice.get(off..end)?))
+}
+
 /// Converts the bytes in `slice` into a `&mut T` abstracted by
 /// [`AlignedMut<'_, T>`].
 /// This is only performed on the first `core::mem::size_of::<T>()` bytes.
@@ -263,6 +383,21 @@ where
     }
 }
 
+/// Converts the bytes at `off` in `slice` into a `&mut T` like
+/// [`convert_slice_to_struct_mut`], returning `None` instead of panicking if
+/// they do not fit in the slice.
+#[inline]
+pub(crate) fn convert_slice_at_to_struct_mut<T>(
+    slice: &mut [c_uchar],
+    off: usize,
+) -> Option<AlignedMut<'_, T>>
+where
+    T: Copy + NoRef,
+{
+    let end = off.checked_add(mem::size_of::<T>())?;
+    Some(convert_slice_to_struct_mut::<T>(slice.get_mut(off..end)?))
+}
+
 /// Read a numeric field that is stored **big-endian** inside a header already
 /// sitting in `payload_slice` at offset `hdr_base`.
 ///
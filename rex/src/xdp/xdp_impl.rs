@@ -4,7 +4,8 @@ use core::{mem, slice};
 
 use crate::base_helper::termination_check;
 pub use crate::bindings::linux::kernel::{
-    ethhdr, iphdr, tcphdr, udphdr, xdp_buff,
+    arphdr, ethhdr, icmp6hdr, icmphdr, iphdr, ipv6hdr, tcphdr, udphdr,
+    vlan_hdr, xdp_buff,
 };
 use crate::bindings::uapi::linux::bpf::bpf_map_type;
 // expose the following constants to the user
@@ -12,45 +13,73 @@ pub use crate::bindings::uapi::linux::bpf::{
     XDP_ABORTED, XDP_DROP, XDP_PASS, XDP_REDIRECT, XDP_TX,
 };
 pub use crate::bindings::uapi::linux::r#in::{IPPROTO_TCP, IPPROTO_UDP};
-use crate::ffi;
+use crate::conntrack::{Conntrack, ConntrackInit, CtOpts};
+use crate::fib::{FibLookupParams, FibLookupResult};
+use crate::linux::errno::EINVAL;
+use crate::packet::{self, PacketCursor};
 use crate::prog_type::rex_prog;
+use crate::socket::{rex_sock_guard, SockTuple};
 use crate::utils::*;
+use crate::{conntrack, csum, ffi, fib, socket};
 
-impl iphdr {
-    #[inline(always)]
-    pub fn saddr(&mut self) -> &mut u32 {
-        unsafe { &mut self.__bindgen_anon_1.__bindgen_anon_1.saddr }
-    }
-
-    pub fn daddr(&mut self) -> &mut u32 {
-        unsafe { &mut self.__bindgen_anon_1.__bindgen_anon_1.daddr }
-    }
-}
-
+/// Recomputes the checksum of an IPv4 header without options from scratch,
+/// see [`crate::csum`] for incremental updates
 #[inline(always)]
 pub fn compute_ip_checksum(ip_header: &mut iphdr) -> u16 {
-    let mut sum: u32 = 0;
     ip_header.check = 0;
 
-    let count = size_of::<iphdr>() >> 1;
-
-    let u16_slice = unsafe {
-        core::slice::from_raw_parts(ip_header as *const _ as *const u16, count)
+    let bytes = unsafe {
+        core::slice::from_raw_parts(
+            ip_header as *const _ as *const u8,
+            size_of::<iphdr>(),
+        )
     };
 
-    for &word in u16_slice {
-        sum += word as u32;
-    }
-
-    sum = (sum & 0xffff) + (sum >> 16);
-    !sum as u16
+    csum::csum_fold(csum::csum_partial(bytes, 0))
 }
 
 pub struct xdp_md<'a> {
+    /// Linear part of the packet, the fragments of a multi-buffer packet are
+    /// accessed with `bpf_xdp_load_bytes` and `bpf_xdp_store_bytes`
     pub data_slice: &'a mut [c_uchar],
+    /// Metadata area in front of the packet data, grown with
+    /// `bpf_xdp_adjust_meta` and readable by TC programs through
+    /// `__sk_buff::meta_slice`. Empty if the driver does not support metadata.
+    pub meta_slice: &'a mut [c_uchar],
     kptr: &'static mut xdp_buff,
 }
 
+impl xdp_md<'_> {
+    /// Re-derives the data and metadata slices from the kernel `xdp_buff`,
+    /// needed after the kernel moves the packet pointers
+    #[inline(always)]
+    fn update_slices(&mut self) {
+        let (data_slice, meta_slice) = unsafe { xdp_slices(self.kptr) };
+        self.data_slice = data_slice;
+        self.meta_slice = meta_slice;
+    }
+}
+
+// NOTE: only covers the linear part of a non-linear xdp_buff
+#[inline(always)]
+unsafe fn xdp_slices<'a>(
+    kptr: &xdp_buff,
+) -> (&'a mut [c_uchar], &'a mut [c_uchar]) {
+    let data = kptr.data as usize;
+    let data_length = kptr.data_end as usize - data;
+
+    // data_meta is set to data + 1 if metadata is not supported
+    let meta = kptr.data_meta as usize;
+    let meta_length = if meta <= data { data - meta } else { 0 };
+
+    unsafe {
+        (
+            slice::from_raw_parts_mut(data as *mut c_uchar, data_length),
+            slice::from_raw_parts_mut(meta as *mut c_uchar, meta_length),
+        )
+    }
+}
+
 // Define accessors of program-accessible fields
 impl xdp_md<'_> {
     #[inline(always)]
@@ -101,15 +130,13 @@ impl xdp {
     #[inline(always)]
     fn convert_ctx(&self, ctx: *mut ()) -> xdp_md {
         let kptr = unsafe { &mut *(ctx as *mut xdp_buff) };
+        let (data_slice, meta_slice) = unsafe { xdp_slices(kptr) };
 
-        // NOTE: not support jumobo frame yet with non-linear xdp_buff
-        let data_length = kptr.data_end as usize - kptr.data as usize;
-
-        let data_slice = unsafe {
-            slice::from_raw_parts_mut(kptr.data as *mut c_uchar, data_length)
-        };
-
-        xdp_md { data_slice, kptr }
+        xdp_md {
+            data_slice,
+            meta_slice,
+            kptr,
+        }
     }
 
     #[inline(always)]
@@ -117,7 +144,8 @@ impl xdp {
         &self,
         ctx: &'b mut xdp_md,
     ) -> AlignedMut<'b, tcphdr> {
-        // NOTE: this assumes packet has ethhdr and iphdr
+        // NOTE: this assumes packet has ethhdr and iphdr without options, use
+        // `rex::packet::PacketCursor` for VLAN tags and IP options
         let begin = mem::size_of::<ethhdr>() + mem::size_of::<iphdr>();
         let end = mem::size_of::<tcphdr>() + begin;
 
@@ -155,10 +183,154 @@ impl xdp {
         )
     }
 
-    // FIX: update based on xdp_md to convert to xdp_buff
-    // pub fn bpf_xdp_adjust_head(&self, xdp: &mut xdp_buff, offset: i32) -> i32
-    // {     unsafe { ffi::bpf_xdp_adjust_head(xdp, offset) }
-    // }
+    /// The outermost VLAN tag, `None` if the packet is untagged
+    #[inline(always)]
+    pub fn vlan_header<'b>(
+        &self,
+        ctx: &'b mut xdp_md,
+    ) -> Option<AlignedMut<'b, vlan_hdr>> {
+        let cursor = PacketCursor::new(&mut ctx.data_slice).eth().ok()?;
+        let off = cursor.offset();
+        if cursor.vlan().ok()?.offset() == off {
+            return None;
+        }
+
+        convert_slice_at_to_struct_mut(&mut ctx.data_slice[..], off)
+    }
+
+    /// The ARP header after the VLAN tags, `None` if the packet is not ARP
+    #[inline(always)]
+    pub fn arp_header<'b>(
+        &self,
+        ctx: &'b mut xdp_md,
+    ) -> Option<AlignedMut<'b, arphdr>> {
+        let cursor = packet::l3_cursor(&mut ctx.data_slice)?;
+        let off = cursor.offset();
+        cursor.arp().ok()?;
+
+        convert_slice_at_to_struct_mut(&mut ctx.data_slice[..], off)
+    }
+
+    /// The IPv6 header after the VLAN tags, `None` if the packet is not IPv6
+    #[inline(always)]
+    pub fn ipv6_header<'b>(
+        &self,
+        ctx: &'b mut xdp_md,
+    ) -> Option<AlignedMut<'b, ipv6hdr>> {
+        let cursor = packet::l3_cursor(&mut ctx.data_slice)?;
+        let off = cursor.offset();
+        cursor.ipv6().ok()?;
+
+        convert_slice_at_to_struct_mut(&mut ctx.data_slice[..], off)
+    }
+
+    /// The ICMP header after the IPv4 header and its options, `None` if the
+    /// packet is not ICMP
+    #[inline(always)]
+    pub fn icmp_header<'b>(
+        &self,
+        ctx: &'b mut xdp_md,
+    ) -> Option<AlignedMut<'b, icmphdr>> {
+        let cursor = packet::l3_cursor(&mut ctx.data_slice)?.ipv4().ok()?;
+        let off = cursor.offset();
+        cursor.icmp().ok()?;
+
+        convert_slice_at_to_struct_mut(&mut ctx.data_slice[..], off)
+    }
+
+    /// The ICMPv6 header after the IPv6 extension headers, `None` if the
+    /// packet is not ICMPv6
+    #[inline(always)]
+    pub fn icmp6_header<'b>(
+        &self,
+        ctx: &'b mut xdp_md,
+    ) -> Option<AlignedMut<'b, icmp6hdr>> {
+        let cursor = packet::l3_cursor(&mut ctx.data_slice)?
+            .ipv6()
+            .and_then(|c| c.ipv6_ext())
+            .ok()?;
+        let off = cursor.offset();
+        cursor.icmp6().ok()?;
+
+        convert_slice_at_to_struct_mut(&mut ctx.data_slice[..], off)
+    }
+
+    /// Moves the start of the packet by `offset` bytes, a negative offset
+    /// grows the packet at the front (e.g. to push an encapsulation header)
+    /// and a positive offset shrinks it. `data_slice` and `meta_slice` are
+    /// updated accordingly.
+    #[inline(always)]
+    pub fn bpf_xdp_adjust_head(&self, ctx: &mut xdp_md, offset: i32) -> Result {
+        let ret = termination_check!(unsafe {
+            ffi::bpf_xdp_adjust_head(ctx.kptr, offset)
+        });
+        if ret != 0 {
+            return Err(ret);
+        }
+
+        ctx.update_slices();
+        Ok(0)
+    }
+
+    /// Moves the start of the metadata area by `offset` bytes, a negative
+    /// offset grows the metadata. `meta_slice` is updated accordingly.
+    #[inline(always)]
+    pub fn bpf_xdp_adjust_meta(&self, ctx: &mut xdp_md, offset: i32) -> Result {
+        let ret = termination_check!(unsafe {
+            ffi::bpf_xdp_adjust_meta(ctx.kptr, offset)
+        });
+        if ret != 0 {
+            return Err(ret);
+        }
+
+        ctx.update_slices();
+        Ok(0)
+    }
+
+    /// Total length of the packet, including all fragments of a
+    /// multi-buffer packet
+    #[inline(always)]
+    pub fn bpf_xdp_get_buff_len(&self, ctx: &mut xdp_md) -> u64 {
+        termination_check!(unsafe { ffi::bpf_xdp_get_buff_len(ctx.kptr) })
+    }
+
+    /// Copies `buf.len()` bytes at `offset` of the packet into `buf`, the
+    /// range can span across fragments
+    #[inline(always)]
+    pub fn bpf_xdp_load_bytes(
+        &self,
+        ctx: &mut xdp_md,
+        offset: u32,
+        buf: &mut [u8],
+    ) -> Result {
+        termination_check!(unsafe {
+            to_result!(ffi::bpf_xdp_load_bytes(
+                ctx.kptr,
+                offset,
+                buf.as_mut_ptr() as *mut (),
+                buf.len() as u32
+            ))
+        })
+    }
+
+    /// Copies `buf` into the packet at `offset`, the range can span across
+    /// fragments
+    #[inline(always)]
+    pub fn bpf_xdp_store_bytes(
+        &self,
+        ctx: &mut xdp_md,
+        offset: u32,
+        buf: &[u8],
+    ) -> Result {
+        termination_check!(unsafe {
+            to_result!(ffi::bpf_xdp_store_bytes(
+                ctx.kptr,
+                offset,
+                buf.as_ptr() as *const (),
+                buf.len() as u32
+            ))
+        })
+    }
 
     // WARN: this function is unsafe
     #[inline(always)]
@@ -171,14 +343,164 @@ impl xdp {
         }
 
         // Update xdp_md fields
-        let data_length = ctx.kptr.data_end as usize - ctx.kptr.data as usize;
+        ctx.update_slices();
+        Ok(0)
+    }
 
-        ctx.data_slice = unsafe {
-            slice::from_raw_parts_mut(
-                ctx.kptr.data as *mut c_uchar,
-                data_length,
+    /// Looks up the route of a packet in the kernel FIB, e.g. to fill in the
+    /// MAC addresses before redirecting it to the egress device
+    #[inline(always)]
+    pub fn bpf_fib_lookup(
+        &self,
+        ctx: &mut xdp_md,
+        params: &FibLookupParams,
+        flags: u32,
+    ) -> core::result::Result<FibLookupResult, i32> {
+        fib::fib_lookup(params, |raw, plen| unsafe {
+            ffi::bpf_xdp_fib_lookup(ctx.kptr as *mut xdp_buff, raw, plen, flags)
+        })
+    }
+
+    /// Looks up a TCP socket (full socket only) matching `tuple` in the
+    /// network namespace of the device the packet arrived on. The reference
+    /// is released when the returned guard is dropped.
+    #[inline(always)]
+    pub fn bpf_sk_lookup_tcp(
+        &self,
+        ctx: &mut xdp_md,
+        tuple: &SockTuple,
+    ) -> Option<rex_sock_guard> {
+        rex_sock_guard::lookup(tuple, |raw, size| unsafe {
+            ffi::bpf_xdp_sk_lookup_tcp(
+                ctx.kptr as *mut xdp_buff,
+                raw,
+                size,
+                socket::CURRENT_NETNS_XDP,
+                0,
+            )
+        })
+    }
+
+    /// Looks up a UDP socket matching `tuple` in the network namespace of
+    /// the device the packet arrived on. The reference is released when the
+    /// returned guard is dropped.
+    #[inline(always)]
+    pub fn bpf_sk_lookup_udp(
+        &self,
+        ctx: &mut xdp_md,
+        tuple: &SockTuple,
+    ) -> Option<rex_sock_guard> {
+        rex_sock_guard::lookup(tuple, |raw, size| unsafe {
+            ffi::bpf_xdp_sk_lookup_udp(
+                ctx.kptr as *mut xdp_buff,
+                raw,
+                size,
+                socket::CURRENT_NETNS_XDP,
+                0,
+            )
+        })
+    }
+
+    /// Like [`Self::bpf_sk_lookup_tcp`], but may also return a request or
+    /// time-wait socket, e.g. to check a SYN cookie against the listener
+    #[inline(always)]
+    pub fn bpf_skc_lookup_tcp(
+        &self,
+        ctx: &mut xdp_md,
+        tuple: &SockTuple,
+    ) -> Option<rex_sock_guard> {
+        rex_sock_guard::lookup(tuple, |raw, size| unsafe {
+            ffi::bpf_xdp_skc_lookup_tcp(
+                ctx.kptr as *mut xdp_buff,
+                raw,
+                size,
+                socket::CURRENT_NETNS_XDP,
+                0,
             )
-        };
+        })
+    }
+
+    /// Looks up the conntrack entry matching `tuple` in the network
+    /// namespace of the packet, the error (e.g. `-ENOENT`) is returned if
+    /// there is none. The reference is released when the returned guard is
+    /// dropped.
+    #[inline(always)]
+    pub fn bpf_ct_lookup(
+        &self,
+        ctx: &mut xdp_md,
+        tuple: &SockTuple,
+        opts: &CtOpts,
+    ) -> core::result::Result<Conntrack, i32> {
+        let (ct, cleanup_idx, dir) = conntrack::ct_acquire(
+            tuple,
+            opts,
+            |raw_tuple, tuple_sz, raw_opts, opts_sz| unsafe {
+                ffi::bpf_xdp_ct_lookup(
+                    ctx.kptr as *mut xdp_buff,
+                    raw_tuple,
+                    tuple_sz,
+                    raw_opts,
+                    opts_sz,
+                )
+            },
+        )?;
+
+        Ok(Conntrack::new(ct, cleanup_idx, dir))
+    }
+
+    /// Allocates a conntrack entry for `tuple`, which can be inserted into
+    /// the table with [`ConntrackInit::insert`] after setting its timeout
+    /// and status
+    #[inline(always)]
+    pub fn bpf_ct_alloc(
+        &self,
+        ctx: &mut xdp_md,
+        tuple: &SockTuple,
+        opts: &CtOpts,
+    ) -> core::result::Result<ConntrackInit, i32> {
+        let (ct, cleanup_idx, _) = conntrack::ct_acquire(
+            tuple,
+            opts,
+            |raw_tuple, tuple_sz, raw_opts, opts_sz| unsafe {
+                ffi::bpf_xdp_ct_alloc(
+                    ctx.kptr as *mut xdp_buff,
+                    raw_tuple,
+                    tuple_sz,
+                    raw_opts,
+                    opts_sz,
+                )
+            },
+        )?;
+
+        Ok(ConntrackInit::new(ct, cleanup_idx))
+    }
+
+    /// Turns the UDP, TCP or ICMP request in `ctx` into a reply to its
+    /// sender carrying the first `payload_len` bytes after the L4 header,
+    /// see [`PacketCursor::reply_in_place`]. Echo requests become echo
+    /// replies. The packet is trimmed to the new length afterwards, the
+    /// caller then returns `XDP_TX`.
+    ///
+    /// To reply with a longer payload, grow the packet with
+    /// [`Self::bpf_xdp_adjust_tail`] before writing the payload.
+    #[inline(always)]
+    pub fn reply_in_place(
+        &self,
+        ctx: &mut xdp_md,
+        payload_len: usize,
+    ) -> Result {
+        let new_len = PacketCursor::new(&mut ctx.data_slice)
+            .eth()
+            .and_then(|c| c.vlan())
+            .and_then(|c| c.ip())
+            .and_then(|c| c.l4())
+            .and_then(|mut c| c.reply_in_place(payload_len))
+            .map_err(|_| -(EINVAL as i32))?;
+
+        let delta = new_len as i32 - ctx.data_slice.len() as i32;
+        if delta != 0 {
+            self.bpf_xdp_adjust_tail(ctx, delta)?;
+        }
 
         Ok(0)
     }
@@ -165,7 +165,7 @@ fn hash_key(
 
             {
                 let mut ip_header_mut = obj.ip_header(ctx);
-                let temp: u32 = *ip_header_mut.saddr();
+                let temp = *ip_header_mut.saddr();
                 *ip_header_mut.saddr() = *ip_header_mut.daddr();
                 *ip_header_mut.daddr() = temp;
             }
@@ -279,9 +279,7 @@ fn bmc_invalidate_cache(obj: &xdp, ctx: &mut xdp_md) -> Result {
     let header_len =
         size_of::<ethhdr>() + size_of::<iphdr>() + size_of::<tcphdr>();
 
-    let tcp_header = obj.tcp_header(ctx);
-    let port = u16::from_be(tcp_header.dest);
-    drop(tcp_header);
+    let port = *obj.tcp_header(ctx).dest();
 
     // start after the tcp header
     let payload = &ctx.data_slice[header_len..];
@@ -343,9 +341,7 @@ fn xdp_rx_filter(obj: &xdp, ctx: &mut xdp_md) -> Result {
                 size_of::<iphdr>() +
                 size_of::<udphdr>() +
                 size_of::<memcached_udp_header>();
-            let udp_header = obj.udp_header(ctx);
-            let port = u16::from_be(udp_header.dest);
-            drop(udp_header);
+            let port = *obj.udp_header(ctx).dest();
 
             let payload = &ctx.data_slice[header_len..];
 
@@ -474,7 +470,7 @@ fn xdp_tx_filter(obj: &sched_cls, skb: &mut __sk_buff) -> Result {
 
     if skb.len() as usize <= header_len ||
         u8::from_be(obj.ip_header(skb).protocol) as u32 != IPPROTO_UDP ||
-        u16::from_be(obj.udp_header(skb).source) != MEMCACHED_PORT ||
+        *obj.udp_header(skb).source() != MEMCACHED_PORT ||
         skb.data_slice.len() < header_len + 6 ||
         !skb.data_slice[header_len..].starts_with(b"VALUE ")
     {
@@ -35,7 +35,6 @@ fn fast_paxos_main(obj: &xdp, ctx: &mut xdp_md) -> Result {
     let header_len =
         size_of::<ethhdr>() + size_of::<iphdr>() + size_of::<udphdr>();
     let iphdr_base = size_of::<ethhdr>();
-    let iphdr_end = iphdr_base + size_of::<iphdr>();
 
     match u8::from_be(read_field!(
         ctx.data_slice,
@@ -49,13 +48,7 @@ fn fast_paxos_main(obj: &xdp, ctx: &mut xdp_md) -> Result {
             // NOTE: currently we only take care of UDP memcached
         }
         IPPROTO_UDP => {
-            let port = u16::from_be(read_field!(
-                ctx.data_slice,
-                iphdr_end,
-                udphdr,
-                dest,
-                u16
-            ));
+            let port = *obj.udp_header(ctx).dest();
 
             let payload = &mut ctx.data_slice[header_len..];
 
@@ -82,7 +75,6 @@ fn fast_broad_cast_main(obj: &sched_cls, skb: &mut __sk_buff) -> Result {
     let header_len =
         size_of::<iphdr>() + size_of::<ethhdr>() + size_of::<udphdr>();
     let iphdr_base = size_of::<ethhdr>();
-    let iphdr_end = iphdr_base + size_of::<iphdr>();
 
     // check if the packet is long enough
     if skb.data_slice.len() <= header_len {
@@ -93,13 +85,7 @@ fn fast_broad_cast_main(obj: &sched_cls, skb: &mut __sk_buff) -> Result {
         as u32 ==
         IPPROTO_UDP
     {
-        let port = u16::from_be(read_field!(
-            skb.data_slice,
-            iphdr_end,
-            udphdr,
-            dest,
-            u16
-        ));
+        let port = *obj.udp_header(skb).dest();
 
         let payload = &skb.data_slice[header_len..];
         // check for the magic bits and Paxos port
@@ -219,7 +205,7 @@ fn handle_udp_fast_broad_cast(obj: &sched_cls, skb: &mut __sk_buff) -> Result {
 
     {
         let ip_header = &mut obj.ip_header(skb);
-        *ip_header.daddr() = replica_info.addr;
+        *ip_header.daddr() = u32be::from_be_bits(replica_info.addr);
         ip_header.check = compute_ip_checksum(ip_header);
     }
 
@@ -477,7 +463,7 @@ fn prepare_fast_reply(
         let ip_header = &mut obj.ip_header(ctx);
         ip_header.tot_len = (new_len + size_of::<iphdr>() as u16).to_be();
         *ip_header.saddr() = *ip_header.daddr();
-        *ip_header.daddr() = leader_info.addr;
+        *ip_header.daddr() = u32be::from_be_bits(leader_info.addr);
         ip_header.check = compute_ip_checksum(ip_header);
     }
 
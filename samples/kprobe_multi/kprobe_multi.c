@@ -0,0 +1,95 @@
+#include <arpa/inet.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#include <librex.h>
+#include <bpf/libbpf.h>
+
+#define EXE "./target/x86_64-unknown-none/release/kprobe_multi"
+
+#define DEBUGFS "/sys/kernel/debug/tracing/"
+
+/* connect(2) a TCP socket and send a UDP datagram to localhost, which fires
+ * tcp_v4_connect and udp_sendmsg
+ */
+static void trigger(void)
+{
+	struct sockaddr_in addr = {
+		.sin_family = AF_INET,
+		.sin_port = htons(9),
+		.sin_addr.s_addr = htonl(INADDR_LOOPBACK),
+	};
+	int fd;
+
+	fd = socket(AF_INET, SOCK_STREAM, 0);
+	if (fd >= 0) {
+		connect(fd, (struct sockaddr *)&addr, sizeof(addr));
+		close(fd);
+	}
+
+	fd = socket(AF_INET, SOCK_DGRAM, 0);
+	if (fd >= 0) {
+		sendto(fd, "rex", 3, 0, (struct sockaddr *)&addr, sizeof(addr));
+		close(fd);
+	}
+}
+
+static void read_trace_pipe(void)
+{
+	int trace_fd;
+
+	trace_fd = open(DEBUGFS "trace_pipe", O_RDONLY, 0);
+	if (trace_fd < 0)
+		return;
+
+	while (1) {
+		static char buf[4096];
+		ssize_t sz;
+
+		sz = read(trace_fd, buf, sizeof(buf) - 1);
+		if (sz > 0) {
+			buf[sz] = 0;
+			puts(buf);
+		}
+	}
+}
+
+int main(void)
+{
+	struct bpf_object *obj;
+	struct bpf_program *prog;
+	struct bpf_link *link = NULL;
+
+	obj = rex_obj_get_bpf(rex_obj_load(EXE));
+	if (!obj) {
+		fprintf(stderr, "Object could not be opened\n");
+		return 1;
+	}
+
+	prog = bpf_object__find_program_by_name(obj, "rex_prog1");
+	if (!prog) {
+		fprintf(stderr, "Program not found\n");
+		return 1;
+	}
+
+	/* librex expands the patterns in the section name and attaches the
+	 * program to all matching functions with a single kprobe.multi link
+	 */
+	link = bpf_program__attach(prog);
+	if (libbpf_get_error(link)) {
+		fprintf(stderr, "ERROR: bpf_program__attach failed\n");
+		link = NULL;
+		return 1;
+	}
+
+	trigger();
+	read_trace_pipe();
+
+	bpf_link__destroy(link);
+	return 0;
+}
@@ -0,0 +1,18 @@
+#![no_std]
+#![no_main]
+
+extern crate rex;
+
+use rex::kprobe::*;
+use rex::pt_regs::PtRegs;
+use rex::{Result, rex_kprobe, rex_printk};
+
+#[rex_kprobe(functions = ["tcp_v*_connect", "udp_sendmsg"])]
+fn rex_prog1(obj: &kprobe, ctx: &mut PtRegs) -> Result {
+    let pid = obj.bpf_get_current_task().map_or(0, |task| task.get_pid());
+    rex_printk!(
+        "Function at {:x} triggered by PID {}\n",
+        obj.bpf_get_func_ip(ctx),
+        pid
+    )
+}
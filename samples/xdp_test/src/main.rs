@@ -15,8 +15,14 @@ use rex::{rex_printk, rex_tc, rex_xdp};
 fn xdp_rx_filter(obj: &xdp, ctx: &mut xdp_md) -> Result {
     let mut ip_header = obj.ip_header(ctx);
 
-    rex_printk!("IP saddr {}\n", Ipv4Addr::from_bits(*ip_header.saddr()))?;
-    rex_printk!("IP daddr {}\n", Ipv4Addr::from_bits(*ip_header.daddr()))?;
+    rex_printk!(
+        "IP saddr {}\n",
+        Ipv4Addr::from_bits(ip_header.saddr().to_host())
+    )?;
+    rex_printk!(
+        "IP daddr {}\n",
+        Ipv4Addr::from_bits(ip_header.daddr().to_host())
+    )?;
 
     match u8::from_be(ip_header.protocol) as u32 {
         IPPROTO_TCP => {
@@ -35,8 +41,14 @@ fn xdp_rx_filter(obj: &xdp, ctx: &mut xdp_md) -> Result {
 fn xdp_tx_filter(obj: &sched_cls, skb: &mut __sk_buff) -> Result {
     let mut ip_header = obj.ip_header(skb);
 
-    rex_printk!("IP saddr {}\n", Ipv4Addr::from_bits(*ip_header.saddr()))?;
-    rex_printk!("IP daddr {}\n", Ipv4Addr::from_bits(*ip_header.daddr()))?;
+    rex_printk!(
+        "IP saddr {}\n",
+        Ipv4Addr::from_bits(ip_header.saddr().to_host())
+    )?;
+    rex_printk!(
+        "IP daddr {}\n",
+        Ipv4Addr::from_bits(ip_header.daddr().to_host())
+    )?;
     if u8::from_be(ip_header.protocol) as u32 == IPPROTO_UDP {
         return rex_printk!("UDP packet!");
     }